@@ -7,21 +7,43 @@ vote on dispute outcomes. It integrates with the escrow and reputation systems.
 Features:
 - Community-driven dispute resolution
 - Weighted voting based on reputation
-- Time-bounded voting periods
+- Randomly drawn, reputation-weighted juror panels
+- Time-bounded commit-reveal voting periods
 - Automatic execution of resolution
-- Incentives for participation
+- Appeal rounds with larger panels
+- Incentives for participation (juror stakes, rewards and slashing)
 - Anti-gaming mechanisms
 */
 
 use concordium_std::*;
 
+use crate::escrow::UpdateParams as EscrowUpdateParams;
+use crate::reputation::{JurorOutcome, ReputationUpdateParams, ScoreAtQuery};
+
 // Contract state
-#[derive(Serialize, SchemaType)]
-pub struct DisputeResolutionState {
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct DisputeResolutionState<S: HasStateApi = StateApi> {
     /// Map of dispute ID to dispute details
-    pub disputes: collections::BTreeMap<DisputeId, DisputeDetails>,
-    /// Map of dispute ID to votes
-    pub votes: collections::BTreeMap<DisputeId, Vec<Vote>>,
+    pub disputes: StateMap<DisputeId, DisputeDetails, S>,
+    /// Map of dispute ID to revealed votes
+    pub votes: StateMap<DisputeId, Vec<Vote>, S>,
+    /// Map of dispute ID to vote commitments
+    pub commitments: StateMap<DisputeId, Vec<VoteCommitment>, S>,
+    /// Number of commitments each juror failed to reveal
+    pub missed_reveals: StateMap<AccountAddress, u32, S>,
+    /// Withdrawable juror balances (returned stakes and rewards)
+    pub juror_balances: StateMap<AccountAddress, Amount, S>,
+    /// Registered juror pool
+    pub jurors: StateMap<AccountAddress, JurorInfo, S>,
+    /// Registered jurors by pool index, so panel draws can sample without scanning the pool
+    pub juror_pool: StateMap<u32, AccountAddress, S>,
+    /// Number of registered jurors
+    pub juror_pool_size: u32,
+    /// Map of dispute ID to drawn juror panel
+    pub panels: StateMap<DisputeId, Vec<AccountAddress>, S>,
+    /// Map of dispute ID to submitted evidence
+    pub evidence: StateMap<DisputeId, Vec<EvidenceItem>, S>,
     /// Admin account
     pub admin: AccountAddress,
     /// Escrow contract address
@@ -32,17 +54,54 @@ pub struct DisputeResolutionState {
     pub next_dispute_id: DisputeId,
     /// Minimum reputation required to vote
     pub min_reputation_to_vote: u64,
-    /// Voting period in hours
+    /// Voting (commit) period in hours
     pub voting_period_hours: u64,
+    /// Reveal period following the commit period in hours
+    pub reveal_period_hours: u64,
+    /// Evidence closes this many hours before the commit period ends
+    pub evidence_cutoff_hours: u64,
     /// Minimum votes required for resolution
     pub min_votes_required: u32,
+    /// Number of jurors drawn for each dispute
+    pub panel_size: u32,
+    /// Time after a resolution during which a losing party can appeal (in hours)
+    pub appeal_window_hours: u64,
+    /// CCD bond required to file an appeal
+    pub appeal_bond: Amount,
     /// Quorum percentage (e.g., 51 for 51%)
     pub quorum_percentage: u8,
+    /// Curve mapping voter reputation to vote weight
+    pub weighting_curve: WeightingCurve,
+    /// Minimum CCD stake locked with each vote
+    pub min_juror_stake: Amount,
+    /// Percentage of stake slashed for voting against a clear majority or not revealing
+    pub slash_percentage: u8,
+    /// Base reward paid from the reward pool to each juror voting with the resolution
+    pub base_juror_reward: Amount,
+    /// CCD funded for base juror rewards
+    pub reward_pool: Amount,
 }
 
 // Dispute identifier
 pub type DisputeId = u64;
 
+// Maximum evidence items each party can submit per dispute
+const MAX_EVIDENCE_PER_PARTY: usize = 10;
+// Maximum length of an evidence URI in bytes
+const MAX_EVIDENCE_URI_LENGTH: usize = 256;
+// Maximum length of an evidence MIME type in bytes
+const MAX_EVIDENCE_MIME_TYPE_LENGTH: usize = 64;
+// Number of appeal rounds allowed after the first verdict
+const MAX_APPEAL_ROUNDS: u32 = 1;
+// Appeal rounds draw this many times the regular panel size
+const APPEAL_PANEL_MULTIPLIER: u32 = 2;
+// Appeal rounds vote for this many times the regular voting period
+const APPEAL_PERIOD_MULTIPLIER: u64 = 2;
+// Hours the committing party has to reveal the panel seed
+const SEED_REVEAL_HOURS: u64 = 24;
+// Maximum jurors sampled from the pool for each panel draw
+const MAX_PANEL_CANDIDATES: u32 = 64;
+
 // Dispute details
 #[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
 pub struct DisputeDetails {
@@ -56,31 +115,76 @@ pub struct DisputeDetails {
     pub amount: Amount,
     /// Reason for dispute
     pub reason: String,
-    /// Evidence provided (IPFS hashes or other references)
-    pub evidence: Vec<String>,
     /// Who initiated the dispute
     pub initiated_by: AccountAddress,
     /// Timestamp when dispute was created
     pub created_at: Timestamp,
-    /// Deadline for voting
+    /// Deadline for submitting evidence
+    pub evidence_deadline: Timestamp,
+    /// Deadline for committing votes
     pub voting_deadline: Timestamp,
+    /// Deadline for revealing committed votes
+    pub reveal_deadline: Timestamp,
     /// Current status
     pub status: DisputeStatus,
     /// Final resolution (if resolved)
     pub resolution: Option<DisputeResolution>,
     /// Total weight of votes received
     pub total_vote_weight: u64,
+    /// SHA-256 of the secret seed revealed to draw the panel
+    pub seed_commit: HashSha2256,
+    /// Timestamp of the seed commit
+    pub seed_committed_at: Timestamp,
+    /// Current round (0 for the first verdict, then one per appeal)
+    pub appeal_round: u32,
+    /// Deadline for appealing the current resolution
+    pub appeal_deadline: Option<Timestamp>,
+    /// Party that filed the appeal
+    pub appellant: Option<AccountAddress>,
+    /// Bond posted with the appeal
+    pub appeal_bond: Amount,
+}
+
+// Evidence submitted by a dispute party
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct EvidenceItem {
+    /// Party that submitted the evidence
+    pub submitter: AccountAddress,
+    /// SHA-256 of the off-chain file
+    pub content_hash: HashSha2256,
+    /// Location of the off-chain file (e.g. IPFS URI)
+    pub uri: String,
+    /// MIME type of the file
+    pub mime_type: String,
+    /// Timestamp of submission
+    pub submitted_at: Timestamp,
+}
+
+// Evidence as submitted by a party
+#[derive(Serialize, SchemaType, Clone)]
+pub struct EvidenceSubmission {
+    pub content_hash: HashSha2256,
+    pub uri: String,
+    pub mime_type: String,
 }
 
 // Dispute status
 #[derive(Serialize, SchemaType, Clone, PartialEq, Eq, Debug)]
 pub enum DisputeStatus {
-    /// Dispute is open for voting
-    Open,
+    /// Waiting for the panel seed to be revealed
+    PanelPending,
+    /// Dispute is open for vote commitments
+    Committing,
+    /// Commit period ended, committed votes are being revealed
+    Revealing,
     /// Voting period ended, resolution pending
     VotingEnded,
-    /// Dispute resolved
+    /// Dispute resolved, open for appeal (first verdicts only) and awaiting settlement
     Resolved,
+    /// Appeal filed, appeal round open for vote commitments
+    Appealed,
+    /// Resolution is final and has been settled in the escrow
+    Final,
     /// Dispute cancelled by admin
     Cancelled,
 }
@@ -96,6 +200,17 @@ pub enum DisputeResolution {
     Split { seller_percentage: u8 },
 }
 
+// Vote weighting curves (all weights are clamped to 1..=100)
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq, Debug)]
+pub enum WeightingCurve {
+    /// One weight unit per 100 reputation points
+    Linear,
+    /// Integer square root of reputation
+    Sqrt,
+    /// Number of bits in reputation (floor(log2) + 1)
+    LogBucket,
+}
+
 // Individual vote
 #[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
 pub struct Vote {
@@ -111,6 +226,34 @@ pub struct Vote {
     pub comment: Option<String>,
 }
 
+// Registered juror
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct JurorInfo {
+    /// Reputation score at registration, used as draw weight
+    pub reputation_snapshot: u64,
+    /// Timestamp of registration
+    pub registered_at: Timestamp,
+    /// Position in the indexed juror pool
+    pub pool_index: u32,
+}
+
+// Hidden vote committed during the commit period
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct VoteCommitment {
+    /// Voter account
+    pub voter: AccountAddress,
+    /// SHA-256 of the dispute ID, voter, serialized vote choice and salt
+    pub commitment: HashSha2256,
+    /// Weight of the vote once revealed (based on reputation at commit time)
+    pub weight: u64,
+    /// CCD stake locked with the vote
+    pub stake: Amount,
+    /// Timestamp of commitment
+    pub committed_at: Timestamp,
+    /// Whether the vote has been revealed
+    pub revealed: bool,
+}
+
 // Vote choices
 #[derive(Serialize, SchemaType, Clone, PartialEq, Eq, Debug)]
 pub enum VoteChoice {
@@ -128,8 +271,24 @@ pub struct InitParams {
     pub admin: AccountAddress,
     pub min_reputation_to_vote: u64,
     pub voting_period_hours: u64,
+    pub reveal_period_hours: u64,
+    pub evidence_cutoff_hours: u64,
     pub min_votes_required: u32,
+    pub panel_size: u32,
     pub quorum_percentage: u8,
+    pub min_juror_stake: Amount,
+    pub slash_percentage: u8,
+    pub appeal_window_hours: u64,
+    pub appeal_bond: Amount,
+}
+
+// Vote commitment query (dispute, voter, choice and salt to be hashed)
+#[derive(Serialize, SchemaType)]
+pub struct VoteCommitmentQuery {
+    pub dispute_id: DisputeId,
+    pub voter: AccountAddress,
+    pub choice: VoteChoice,
+    pub salt: [u8; 32],
 }
 
 // Contract update parameters
@@ -142,22 +301,50 @@ pub enum UpdateParams {
         seller: AccountAddress,
         amount: Amount,
         reason: String,
-        evidence: Vec<String>,
+        evidence: Vec<EvidenceSubmission>,
+        /// SHA-256 of a secret seed from the dispute creator, revealed in a later block to draw the panel
+        seed_commit: HashSha2256,
+    },
+    /// Draw the juror panel by revealing the committed seed (without a seed once the reveal period has passed)
+    DrawPanel {
+        dispute_id: DisputeId,
+        seed: Option<[u8; 32]>,
     },
-    /// Cast a vote on a dispute
-    Vote {
+    /// Join the juror pool (reputation is snapshotted at registration)
+    RegisterJuror,
+    /// Leave the juror pool
+    UnregisterJuror,
+    /// Commit a hidden vote on a dispute, locking the attached CCD as stake
+    CommitVote {
+        dispute_id: DisputeId,
+        /// SHA-256 of the dispute ID, voter, serialized vote choice and salt
+        commitment: HashSha2256,
+    },
+    /// Reveal a committed vote after the commit period
+    RevealVote {
         dispute_id: DisputeId,
         choice: VoteChoice,
+        salt: [u8; 32],
         comment: Option<String>,
     },
     /// Resolve a dispute (after voting period)
     ResolveDispute {
         dispute_id: DisputeId,
     },
+    /// Appeal a resolution, posting the attached CCD as appeal bond (losing party only)
+    AppealDispute {
+        dispute_id: DisputeId,
+        /// SHA-256 of a secret seed from the appellant, revealed in a later block to draw the appeal panel
+        seed_commit: HashSha2256,
+    },
+    /// Settle an appeal verdict, an unappealed resolution once the appeal window has closed, or the first verdict of a lapsed appeal
+    FinalizeDispute {
+        dispute_id: DisputeId,
+    },
     /// Add evidence to an existing dispute
     AddEvidence {
         dispute_id: DisputeId,
-        evidence: String,
+        evidence: EvidenceSubmission,
     },
     /// Cancel dispute (admin only)
     CancelDispute {
@@ -167,8 +354,23 @@ pub enum UpdateParams {
     UpdateParameters {
         min_reputation_to_vote: Option<u64>,
         voting_period_hours: Option<u64>,
+        reveal_period_hours: Option<u64>,
+        evidence_cutoff_hours: Option<u64>,
         min_votes_required: Option<u32>,
+        panel_size: Option<u32>,
         quorum_percentage: Option<u8>,
+        weighting_curve: Option<WeightingCurve>,
+        min_juror_stake: Option<Amount>,
+        slash_percentage: Option<u8>,
+        appeal_window_hours: Option<u64>,
+        appeal_bond: Option<Amount>,
+        base_juror_reward: Option<Amount>,
+    },
+    /// Add the attached CCD to the pool funding base juror rewards
+    FundRewardPool,
+    /// Withdraw returned stakes and rewards
+    WithdrawJurorBalance {
+        amount: Amount,
     },
     /// Set contract addresses (admin only)
     SetContractAddresses {
@@ -198,6 +400,48 @@ pub enum DisputeError {
     InsufficientVotes,
     /// Invalid split percentage
     InvalidSplitPercentage,
+    /// No vote commitment found for this voter
+    CommitmentNotFound,
+    /// Revealed vote does not match the commitment
+    CommitmentMismatch,
+    /// Attached stake is below the minimum juror stake
+    InsufficientStake,
+    /// CCD attached to an operation that does not take a stake
+    UnexpectedAmount,
+    /// Juror balance too low for withdrawal
+    InsufficientBalance,
+    /// Transfer error
+    TransferError,
+    /// Voter was not drawn for this dispute's panel
+    NotOnPanel,
+    /// Not enough registered jurors to draw a panel
+    InsufficientJurors,
+    /// Panel size is zero or below the minimum votes required
+    InvalidPanelSize,
+    /// Revealed seed does not match the seed commit
+    SeedMismatch,
+    /// Seed must be revealed in a later block than its commit
+    SeedRevealTooEarly,
+    /// Seed reveal period has ended
+    SeedRevealClosed,
+    /// Seed reveal period is still open
+    SeedRevealOpen,
+    /// Account is not a registered juror
+    JurorNotRegistered,
+    /// Appeal window has closed
+    AppealWindowClosed,
+    /// Appeal window is still open
+    AppealWindowOpen,
+    /// Attached appeal bond is too low
+    InsufficientAppealBond,
+    /// Party reached the evidence submission limit
+    EvidenceLimitReached,
+    /// Evidence URI or MIME type too long
+    EvidenceTooLarge,
+    /// Evidence deadline has passed
+    EvidenceDeadlinePassed,
+    /// Deadline is out of the timestamp range
+    TimestampOverflow,
     /// Contract address not set
     ContractNotSet,
     /// Parse error
@@ -217,7 +461,26 @@ pub enum DisputeEvent {
         seller: AccountAddress,
         amount: Amount,
     },
-    /// Vote cast
+    /// Juror joined the pool
+    JurorRegistered {
+        juror: AccountAddress,
+        reputation_snapshot: u64,
+    },
+    /// Juror left the pool
+    JurorUnregistered {
+        juror: AccountAddress,
+    },
+    /// Juror panel drawn for a dispute
+    PanelDrawn {
+        dispute_id: DisputeId,
+        jurors: Vec<AccountAddress>,
+    },
+    /// Vote committed (choice stays hidden until revealed)
+    VoteCommitted {
+        dispute_id: DisputeId,
+        voter: AccountAddress,
+    },
+    /// Vote revealed
     VoteCast {
         dispute_id: DisputeId,
         voter: AccountAddress,
@@ -228,7 +491,8 @@ pub enum DisputeEvent {
     EvidenceAdded {
         dispute_id: DisputeId,
         added_by: AccountAddress,
-        evidence: String,
+        content_hash: HashSha2256,
+        uri: String,
     },
     /// Dispute resolved
     DisputeResolved {
@@ -237,30 +501,92 @@ pub enum DisputeEvent {
         total_votes: u32,
         total_weight: u64,
     },
+    /// Appeal filed
+    AppealFiled {
+        dispute_id: DisputeId,
+        appellant: AccountAddress,
+        appeal_bond: Amount,
+        appeal_round: u32,
+    },
+    /// Resolution became final and was settled in the escrow
+    DisputeFinalized {
+        dispute_id: DisputeId,
+        resolution: DisputeResolution,
+    },
     /// Dispute cancelled
     DisputeCancelled {
         dispute_id: DisputeId,
     },
+    /// Juror stake slashed
+    StakeSlashed {
+        dispute_id: DisputeId,
+        voter: AccountAddress,
+        amount: Amount,
+    },
+    /// Juror rewarded for voting with the resolution
+    JurorRewarded {
+        dispute_id: DisputeId,
+        voter: AccountAddress,
+        reward: Amount,
+    },
+    /// Base juror reward pool funded
+    RewardPoolFunded {
+        amount: Amount,
+        reward_pool: Amount,
+    },
+    /// Juror balance withdrawn
+    JurorBalanceWithdrawn {
+        juror: AccountAddress,
+        amount: Amount,
+    },
+    /// Committed vote was not revealed in time
+    RevealMissed {
+        dispute_id: DisputeId,
+        voter: AccountAddress,
+        missed_reveals: u32,
+    },
 }
 
 type ContractResult<T> = Result<T, DisputeError>;
 
 /// Initialize the dispute resolution contract
 #[init(contract = "credify_dispute", parameter = "InitParams")]
-fn init(_ctx: &InitContext, _state_builder: &mut StateBuilder) -> InitResult<DisputeResolutionState> {
+fn init(_ctx: &InitContext, state_builder: &mut StateBuilder) -> InitResult<DisputeResolutionState> {
     let params: InitParams = _ctx.parameter_cursor().get()?;
     
+    if params.panel_size == 0 || params.panel_size < params.min_votes_required {
+        return Err(DisputeError::InvalidPanelSize.into());
+    }
+    
     let state = DisputeResolutionState {
-        disputes: collections::BTreeMap::new(),
-        votes: collections::BTreeMap::new(),
+        disputes: state_builder.new_map(),
+        votes: state_builder.new_map(),
+        commitments: state_builder.new_map(),
+        missed_reveals: state_builder.new_map(),
+        juror_balances: state_builder.new_map(),
+        jurors: state_builder.new_map(),
+        juror_pool: state_builder.new_map(),
+        juror_pool_size: 0,
+        panels: state_builder.new_map(),
+        evidence: state_builder.new_map(),
         admin: params.admin,
         escrow_contract: None,
         reputation_contract: None,
         next_dispute_id: 0,
         min_reputation_to_vote: params.min_reputation_to_vote,
         voting_period_hours: params.voting_period_hours,
+        reveal_period_hours: params.reveal_period_hours,
+        evidence_cutoff_hours: params.evidence_cutoff_hours,
         min_votes_required: params.min_votes_required,
+        panel_size: params.panel_size,
+        appeal_window_hours: params.appeal_window_hours,
+        appeal_bond: params.appeal_bond,
         quorum_percentage: params.quorum_percentage,
+        weighting_curve: WeightingCurve::Sqrt,
+        min_juror_stake: params.min_juror_stake,
+        slash_percentage: params.slash_percentage.min(100),
+        base_juror_reward: Amount::zero(),
+        reward_pool: Amount::zero(),
     };
     
     Ok(state)
@@ -271,16 +597,32 @@ fn init(_ctx: &InitContext, _state_builder: &mut StateBuilder) -> InitResult<Dis
     contract = "credify_dispute",
     name = "update",
     parameter = "UpdateParams",
+    return_value = "Option<DisputeId>",
     error = "DisputeError",
     enable_logger,
-    mutable
+    mutable,
+    payable,
+    crypto_primitives
 )]
 fn update(
     ctx: &ReceiveContext,
     host: &mut Host<DisputeResolutionState>,
+    amount: Amount,
     logger: &mut Logger,
-) -> ContractResult<()> {
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<Option<DisputeId>> {
     let params: UpdateParams = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
+    
+    // Only vote commitments (juror stake), appeals (appeal bond) and reward pool funding accept CCD
+    if amount > Amount::zero()
+        && !matches!(
+            params,
+            UpdateParams::CommitVote { .. } | UpdateParams::AppealDispute { .. } | UpdateParams::FundRewardPool
+        )
+    {
+        return Err(DisputeError::UnexpectedAmount);
+    }
+    
     let state = host.state_mut();
     
     match params {
@@ -291,10 +633,11 @@ fn update(
             amount,
             reason,
             evidence,
+            seed_commit,
         } => {
             // Only escrow contract or admin can create disputes
             if let Some(escrow_contract) = state.escrow_contract {
-                if ctx.sender() != AccountAddress::Contract(escrow_contract) && ctx.sender() != state.admin {
+                if ctx.sender() != Address::Contract(escrow_contract) && ctx.sender() != state.admin {
                     return Err(DisputeError::Unauthorized);
                 }
             } else if ctx.sender() != state.admin {
@@ -303,25 +646,58 @@ fn update(
             
             let dispute_id = state.next_dispute_id;
             let created_at = ctx.metadata().slot_time();
-            let voting_deadline = created_at.add_duration(Duration::from_hours(state.voting_period_hours));
+            let voting_deadline = created_at
+                .checked_add(Duration::from_hours(state.voting_period_hours))
+                .ok_or(DisputeError::TimestampOverflow)?;
+            let reveal_deadline = voting_deadline
+                .checked_add(Duration::from_hours(state.reveal_period_hours))
+                .ok_or(DisputeError::TimestampOverflow)?;
+            let evidence_deadline = evidence_deadline(created_at, voting_deadline, state.evidence_cutoff_hours);
             
+            // Evidence submitted with the dispute is attributed to the party that raised it
+            let initiated_by = ctx.invoker();
+            if evidence.len() > MAX_EVIDENCE_PER_PARTY {
+                return Err(DisputeError::EvidenceLimitReached);
+            }
+            let mut evidence_items = Vec::new();
+            for submission in evidence {
+                evidence_items.push(evidence_item(initiated_by, submission, created_at)?);
+            }
+            
+            // A pool smaller than the minimum votes can never draw a panel that reaches quorum
+            if state.juror_pool_size == 0 || state.juror_pool_size < state.min_votes_required {
+                return Err(DisputeError::InsufficientJurors);
+            }
+            
+            // The panel is drawn and the deadlines restarted once the seed is revealed
             let dispute = DisputeDetails {
                 escrow_id,
                 buyer,
                 seller,
                 amount,
                 reason: reason.clone(),
-                evidence: evidence.clone(),
-                initiated_by: ctx.sender(),
+                // The account that signed the transaction, also when relayed by the escrow contract
+                initiated_by,
                 created_at,
+                evidence_deadline,
                 voting_deadline,
-                status: DisputeStatus::Open,
+                reveal_deadline,
+                status: DisputeStatus::PanelPending,
                 resolution: None,
                 total_vote_weight: 0,
+                seed_commit,
+                seed_committed_at: created_at,
+                appeal_round: 0,
+                appeal_deadline: None,
+                appellant: None,
+                appeal_bond: Amount::zero(),
             };
             
             state.disputes.insert(dispute_id, dispute);
             state.votes.insert(dispute_id, Vec::new());
+            state.commitments.insert(dispute_id, Vec::new());
+            state.panels.insert(dispute_id, Vec::new());
+            state.evidence.insert(dispute_id, evidence_items);
             state.next_dispute_id += 1;
             
             logger.log(&DisputeEvent::DisputeCreated {
@@ -332,45 +708,262 @@ fn update(
                 amount,
             })?;
             
-            Ok(())
+            Ok(Some(dispute_id))
         }
         
-        UpdateParams::Vote {
+        UpdateParams::DrawPanel { dispute_id, seed } => {
+            let now = ctx.metadata().slot_time();
+            let mut dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            
+            if dispute.status != DisputeStatus::PanelPending {
+                return Err(DisputeError::InvalidStatus);
+            }
+            
+            // The draw only depends on the committed seed and fixed timestamps,
+            // so the choice of draw block cannot be used to grind the panel
+            let seed_deadline = dispute.seed_committed_at
+                .checked_add(Duration::from_hours(SEED_REVEAL_HOURS))
+                .ok_or(DisputeError::TimestampOverflow)?;
+            let committer = if dispute.appeal_round == 0 {
+                dispute.initiated_by
+            } else {
+                dispute.appellant.ok_or(DisputeError::InvalidStatus)?
+            };
+            let (draw_seed, seed_time) = match seed {
+                Some(seed) => {
+                    if ctx.invoker() != committer {
+                        return Err(DisputeError::Unauthorized);
+                    }
+                    if now <= dispute.seed_committed_at {
+                        return Err(DisputeError::SeedRevealTooEarly);
+                    }
+                    if now > seed_deadline {
+                        return Err(DisputeError::SeedRevealClosed);
+                    }
+                    if crypto_primitives.hash_sha2_256(&seed) != dispute.seed_commit {
+                        return Err(DisputeError::SeedMismatch);
+                    }
+                    (seed, dispute.seed_committed_at)
+                }
+                // Anyone can draw from the commit and the reveal deadline if the seed was never revealed,
+                // an unrevealed appeal lapses instead (see FinalizeDispute)
+                None => {
+                    if dispute.appeal_round > 0 {
+                        return Err(DisputeError::InvalidStatus);
+                    }
+                    if now <= seed_deadline {
+                        return Err(DisputeError::SeedRevealOpen);
+                    }
+                    (dispute.seed_commit.0, seed_deadline)
+                }
+            };
+            
+            // Draw the panel from a sample of the pool, excluding the parties
+            let seed = panel_seed(crypto_primitives, &draw_seed, seed_time, dispute_id);
+            let candidates = sample_candidates(
+                crypto_primitives,
+                &state.juror_pool,
+                &state.jurors,
+                state.juror_pool_size,
+                &seed,
+                dispute.buyer,
+                dispute.seller,
+            );
+            
+            // Appeal rounds use a larger panel and a longer voting period
+            let (panel_size, voting_period_hours, status) = if dispute.appeal_round == 0 {
+                (state.panel_size, state.voting_period_hours, DisputeStatus::Committing)
+            } else {
+                (
+                    state.panel_size.saturating_mul(APPEAL_PANEL_MULTIPLIER),
+                    state.voting_period_hours.saturating_mul(APPEAL_PERIOD_MULTIPLIER),
+                    DisputeStatus::Appealed,
+                )
+            };
+            let panel = draw_panel(crypto_primitives, candidates, panel_size, &seed);
+            
+            // A panel smaller than the minimum votes can never reach quorum
+            if panel.is_empty() || panel.len() < state.min_votes_required as usize {
+                return Err(DisputeError::InsufficientJurors);
+            }
+            
+            // The commit period starts with the draw
+            dispute.voting_deadline = now
+                .checked_add(Duration::from_hours(voting_period_hours))
+                .ok_or(DisputeError::TimestampOverflow)?;
+            dispute.evidence_deadline = evidence_deadline(now, dispute.voting_deadline, state.evidence_cutoff_hours);
+            dispute.reveal_deadline = dispute.voting_deadline
+                .checked_add(Duration::from_hours(state.reveal_period_hours))
+                .ok_or(DisputeError::TimestampOverflow)?;
+            dispute.status = status;
+            
+            state.panels.insert(dispute_id, panel.clone());
+            
+            logger.log(&DisputeEvent::PanelDrawn {
+                dispute_id,
+                jurors: panel,
+            })?;
+            
+            Ok(None)
+        }
+        
+        UpdateParams::RegisterJuror => {
+            let juror = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
+            
+            // Registering again refreshes the reputation snapshot
+            let reputation = get_voter_reputation(host, juror)?;
+            let state = host.state_mut();
+            if reputation < state.min_reputation_to_vote {
+                return Err(DisputeError::InsufficientReputation);
+            }
+            
+            let pool_index = match state.jurors.get(&juror).map(|info| info.pool_index) {
+                Some(index) => index,
+                None => {
+                    let index = state.juror_pool_size;
+                    state.juror_pool.insert(index, juror);
+                    state.juror_pool_size += 1;
+                    index
+                }
+            };
+            state.jurors.insert(juror, JurorInfo {
+                reputation_snapshot: reputation,
+                registered_at: ctx.metadata().slot_time(),
+                pool_index,
+            });
+            
+            logger.log(&DisputeEvent::JurorRegistered {
+                juror,
+                reputation_snapshot: reputation,
+            })?;
+            
+            Ok(None)
+        }
+        
+        UpdateParams::UnregisterJuror => {
+            let juror = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
+            
+            // Already drawn panels are not affected
+            let info = state.jurors.remove_and_get(&juror).ok_or(DisputeError::JurorNotRegistered)?;
+            
+            // Move the last juror into the freed slot to keep the pool indices contiguous
+            let last_index = state.juror_pool_size - 1;
+            if info.pool_index != last_index {
+                if let Some(last_juror) = state.juror_pool.get(&last_index).map(|j| *j) {
+                    state.juror_pool.insert(info.pool_index, last_juror);
+                    if let Some(mut last_info) = state.jurors.get_mut(&last_juror) {
+                        last_info.pool_index = info.pool_index;
+                    }
+                }
+            }
+            state.juror_pool.remove(&last_index);
+            state.juror_pool_size = last_index;
+            
+            logger.log(&DisputeEvent::JurorUnregistered { juror })?;
+            
+            Ok(None)
+        }
+        
+        UpdateParams::CommitVote {
             dispute_id,
-            choice,
-            comment,
+            commitment,
         } => {
-            let dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            let dispute = state.disputes.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
             
-            // Check if dispute is still open for voting
-            if dispute.status != DisputeStatus::Open {
+            // Check if dispute is still open for commitments
+            if dispute.status != DisputeStatus::Committing && dispute.status != DisputeStatus::Appealed {
                 return Err(DisputeError::InvalidStatus);
             }
             
-            // Check if voting period has ended
+            // Check if commit period has ended
             if ctx.metadata().slot_time() > dispute.voting_deadline {
-                dispute.status = DisputeStatus::VotingEnded;
                 return Err(DisputeError::VotingEnded);
             }
             
             // Prevent parties from voting on their own dispute
-            let voter = ctx.sender();
+            let voter = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
             if voter == dispute.buyer || voter == dispute.seller {
                 return Err(DisputeError::Unauthorized);
             }
             
-            // Check if user already voted
-            let existing_votes = state.votes.get(&dispute_id).unwrap();
-            if existing_votes.iter().any(|v| v.voter == voter) {
+            // Only jurors drawn for this dispute may vote
+            let panel = state.panels.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            if !panel.contains(&voter) {
+                return Err(DisputeError::NotOnPanel);
+            }
+            
+            if amount < state.min_juror_stake {
+                return Err(DisputeError::InsufficientStake);
+            }
+            
+            // Check if user already committed
+            let existing_commitments = state.commitments.get(&dispute_id).unwrap();
+            if existing_commitments.iter().any(|c| c.voter == voter) {
                 return Err(DisputeError::AlreadyVoted);
             }
             
-            // Get voter's reputation (simplified - in real implementation, call reputation contract)
-            let voter_reputation = get_voter_reputation(host, voter)?;
+            // Weigh by reputation at dispute creation so score farmed mid-dispute does not count
+            let voter_reputation = get_voter_reputation_at(host, voter, dispute.created_at)?;
             if voter_reputation < state.min_reputation_to_vote {
                 return Err(DisputeError::InsufficientReputation);
             }
             
+            // Weight is fixed at commit time, halved for every reveal the juror missed before
+            let missed_reveals = state.missed_reveals.get(&voter).map(|m| *m).unwrap_or(0);
+            let vote_weight = calculate_vote_weight(voter_reputation, &state.weighting_curve)
+                .checked_shr(missed_reveals)
+                .unwrap_or(0)
+                .max(1);
+            
+            let mut commitments = state.commitments.get_mut(&dispute_id).unwrap();
+            commitments.push(VoteCommitment {
+                voter,
+                commitment,
+                weight: vote_weight,
+                stake: amount,
+                committed_at: ctx.metadata().slot_time(),
+                revealed: false,
+            });
+            
+            logger.log(&DisputeEvent::VoteCommitted { dispute_id, voter })?;
+            
+            Ok(None)
+        }
+        
+        UpdateParams::RevealVote {
+            dispute_id,
+            choice,
+            salt,
+            comment,
+        } => {
+            let mut dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            
+            if !matches!(
+                dispute.status,
+                DisputeStatus::Committing | DisputeStatus::Revealing | DisputeStatus::Appealed
+            ) {
+                return Err(DisputeError::InvalidStatus);
+            }
+            
+            // Votes can only be revealed between the commit and reveal deadlines
+            let now = ctx.metadata().slot_time();
+            if now <= dispute.voting_deadline {
+                return Err(DisputeError::VotingStillActive);
+            }
+            if now > dispute.reveal_deadline {
+                return Err(DisputeError::VotingEnded);
+            }
+            dispute.status = DisputeStatus::Revealing;
+            
             // Validate split percentage if applicable
             if let VoteChoice::Split { seller_percentage } = &choice {
                 if *seller_percentage > 100 {
@@ -378,18 +971,35 @@ fn update(
                 }
             }
             
-            // Create vote with weight based on reputation
-            let vote_weight = calculate_vote_weight(voter_reputation);
+            let voter = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
+            let mut commitments = state.commitments.get_mut(&dispute_id).unwrap();
+            let commitment = commitments
+                .iter_mut()
+                .find(|c| c.voter == voter)
+                .ok_or(DisputeError::CommitmentNotFound)?;
+            if commitment.revealed {
+                return Err(DisputeError::AlreadyVoted);
+            }
+            // Binding the commitment to the dispute and the revealing account stops jurors copying commitments
+            if vote_commitment(crypto_primitives, dispute_id, ctx.invoker(), &choice, &salt) != commitment.commitment {
+                return Err(DisputeError::CommitmentMismatch);
+            }
+            commitment.revealed = true;
+            
+            let vote_weight = commitment.weight;
             let vote = Vote {
                 voter,
                 choice: choice.clone(),
                 weight: vote_weight,
-                timestamp: ctx.metadata().slot_time(),
+                timestamp: now,
                 comment,
             };
             
             // Add vote
-            let votes = state.votes.get_mut(&dispute_id).unwrap();
+            let mut votes = state.votes.get_mut(&dispute_id).unwrap();
             votes.push(vote);
             
             // Update total vote weight
@@ -402,80 +1012,283 @@ fn update(
                 weight: vote_weight,
             })?;
             
-            Ok(())
+            Ok(None)
         }
         
         UpdateParams::ResolveDispute { dispute_id } => {
-            let dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            let now = ctx.metadata().slot_time();
+            let appeal_window_hours = state.appeal_window_hours;
             
-            // Check if voting period has ended
-            if ctx.metadata().slot_time() <= dispute.voting_deadline {
-                return Err(DisputeError::VotingStillActive);
-            }
+            let (resolution, total_weight, votes, forfeited_bond) = {
+                let mut dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+                
+                // Check if reveal period has ended
+                if now <= dispute.reveal_deadline {
+                    return Err(DisputeError::VotingStillActive);
+                }
+                
+                if !matches!(
+                    dispute.status,
+                    DisputeStatus::Committing
+                        | DisputeStatus::Revealing
+                        | DisputeStatus::VotingEnded
+                        | DisputeStatus::Appealed
+                ) {
+                    return Err(DisputeError::InvalidStatus);
+                }
+                
+                // Unrevealed commitments are excluded from the tally
+                let votes = state.votes.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+                
+                // Check if minimum votes requirement is met
+                if votes.len() < state.min_votes_required as usize {
+                    return Err(DisputeError::InsufficientVotes);
+                }
+                
+                // Calculate resolution based on weighted votes
+                let resolution = calculate_resolution(&votes, state.quorum_percentage)?;
+                
+                // First verdicts can be appealed, appeal verdicts can be finalized right away.
+                // Escrow settlement is left to FinalizeDispute, so a failing escrow call cannot
+                // revert the verdict and juror rewards and can be retried.
+                let mut forfeited_bond = Amount::zero();
+                dispute.status = DisputeStatus::Resolved;
+                if dispute.appeal_round == 0 {
+                    let appeal_deadline = now
+                        .checked_add(Duration::from_hours(appeal_window_hours))
+                        .ok_or(DisputeError::TimestampOverflow)?;
+                    dispute.appeal_deadline = Some(appeal_deadline);
+                } else {
+                    dispute.appeal_deadline = None;
+                    
+                    // The bond is returned if the appeal changed the verdict, otherwise it goes to the jurors
+                    if dispute.resolution.as_ref() != Some(&resolution) {
+                        if let Some(appellant) = dispute.appellant {
+                            credit_juror(&mut state.juror_balances, appellant, dispute.appeal_bond);
+                        }
+                    } else {
+                        forfeited_bond = dispute.appeal_bond;
+                    }
+                }
+                dispute.resolution = Some(resolution.clone());
+                
+                // Penalize jurors who committed but did not reveal
+                let commitments = state.commitments.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+                for commitment in commitments.iter().filter(|c| !c.revealed) {
+                    let missed_reveals = state.missed_reveals.entry(commitment.voter).or_insert(0).modify(|missed| {
+                        *missed += 1;
+                        *missed
+                    });
+                    
+                    logger.log(&DisputeEvent::RevealMissed {
+                        dispute_id,
+                        voter: commitment.voter,
+                        missed_reveals,
+                    })?;
+                }
+                
+                (resolution, dispute.total_vote_weight, votes.clone(), forfeited_bond)
+            };
             
-            if dispute.status != DisputeStatus::Open && dispute.status != DisputeStatus::VotingEnded {
+            logger.log(&DisputeEvent::DisputeResolved {
+                dispute_id,
+                resolution: resolution.clone(),
+                total_votes: votes.len() as u32,
+                total_weight,
+            })?;
+            
+            // Return stakes, slash and reward jurors
+            let outcomes = distribute_voting_rewards(host, logger, dispute_id, &resolution, &votes, forfeited_bond)?;
+            report_juror_outcomes(host, outcomes);
+            
+            Ok(None)
+        }
+        
+        UpdateParams::AppealDispute {
+            dispute_id,
+            seed_commit,
+        } => {
+            let appellant = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
+            let now = ctx.metadata().slot_time();
+            
+            let mut dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            
+            if dispute.status != DisputeStatus::Resolved || dispute.appeal_round >= MAX_APPEAL_ROUNDS {
                 return Err(DisputeError::InvalidStatus);
             }
             
-            let votes = state.votes.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
-            
-            // Check if minimum votes requirement is met
-            if votes.len() < state.min_votes_required as usize {
-                return Err(DisputeError::InsufficientVotes);
+            match dispute.appeal_deadline {
+                Some(deadline) if now <= deadline => {}
+                _ => return Err(DisputeError::AppealWindowClosed),
             }
             
-            // Calculate resolution based on weighted votes
-            let resolution = calculate_resolution(votes, state.quorum_percentage)?;
+            // Only the losing party can appeal (either party for a split)
+            let may_appeal = match dispute.resolution {
+                Some(DisputeResolution::FavorBuyer) => appellant == dispute.seller,
+                Some(DisputeResolution::FavorSeller) => appellant == dispute.buyer,
+                Some(DisputeResolution::Split { .. }) => appellant == dispute.buyer || appellant == dispute.seller,
+                None => false,
+            };
+            if !may_appeal {
+                return Err(DisputeError::Unauthorized);
+            }
             
-            // Update dispute status
-            dispute.status = DisputeStatus::Resolved;
-            dispute.resolution = Some(resolution.clone());
+            if amount < state.appeal_bond {
+                return Err(DisputeError::InsufficientAppealBond);
+            }
             
-            // Call escrow contract to execute resolution
-            if let Some(escrow_contract) = state.escrow_contract {
-                // In a real implementation, this would call the escrow contract's resolve_dispute method
-                // For now, we'll just log the resolution
+            // A pool smaller than the minimum votes can never draw a panel that reaches quorum
+            if state.juror_pool_size == 0 || state.juror_pool_size < state.min_votes_required {
+                return Err(DisputeError::InsufficientJurors);
             }
             
-            logger.log(&DisputeEvent::DisputeResolved {
+            // The appeal panel is drawn once the appellant reveals the seed in a later block
+            dispute.appeal_round += 1;
+            dispute.appeal_deadline = None;
+            dispute.appellant = Some(appellant);
+            dispute.appeal_bond = amount;
+            dispute.total_vote_weight = 0;
+            dispute.seed_commit = seed_commit;
+            dispute.seed_committed_at = now;
+            dispute.status = DisputeStatus::PanelPending;
+            let appeal_round = dispute.appeal_round;
+            
+            // Start the appeal round with a fresh panel, commitments and votes
+            state.votes.insert(dispute_id, Vec::new());
+            state.commitments.insert(dispute_id, Vec::new());
+            state.panels.insert(dispute_id, Vec::new());
+            
+            logger.log(&DisputeEvent::AppealFiled {
                 dispute_id,
-                resolution,
-                total_votes: votes.len() as u32,
-                total_weight: dispute.total_vote_weight,
+                appellant,
+                appeal_bond: amount,
+                appeal_round,
             })?;
             
-            // Distribute rewards to voters (simplified)
-            distribute_voting_rewards(host, dispute_id, votes)?;
+            Ok(None)
+        }
+        
+        UpdateParams::FinalizeDispute { dispute_id } => {
+            let now = ctx.metadata().slot_time();
+            let min_votes_required = state.min_votes_required;
+            let quorum_percentage = state.quorum_percentage;
+            
+            let (escrow_id, resolution) = {
+                let mut dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+                
+                match dispute.status {
+                    // Appeal verdicts have no appeal window
+                    DisputeStatus::Resolved if dispute.appeal_round >= MAX_APPEAL_ROUNDS => {}
+                    DisputeStatus::Resolved => match dispute.appeal_deadline {
+                        Some(deadline) if now > deadline => {}
+                        _ => return Err(DisputeError::AppealWindowOpen),
+                    },
+                    // An appeal whose seed was never revealed lapses and its bond is forfeited
+                    DisputeStatus::PanelPending if dispute.appeal_round > 0 => {
+                        let seed_deadline = dispute.seed_committed_at
+                            .checked_add(Duration::from_hours(SEED_REVEAL_HOURS))
+                            .ok_or(DisputeError::TimestampOverflow)?;
+                        if now <= seed_deadline {
+                            return Err(DisputeError::SeedRevealOpen);
+                        }
+                        let admin = state.admin;
+                        credit_juror(&mut state.juror_balances, admin, dispute.appeal_bond);
+                        dispute.appeal_bond = Amount::zero();
+                    }
+                    // An appeal round that cannot reach a verdict by its reveal deadline lapses,
+                    // returning the appeal bond and juror stakes
+                    DisputeStatus::Appealed | DisputeStatus::Revealing | DisputeStatus::VotingEnded
+                        if dispute.appeal_round > 0 =>
+                    {
+                        if now <= dispute.reveal_deadline {
+                            return Err(DisputeError::VotingStillActive);
+                        }
+                        {
+                            let votes = state.votes.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+                            if votes.len() >= min_votes_required as usize
+                                && calculate_resolution(&votes, quorum_percentage).is_ok()
+                            {
+                                return Err(DisputeError::InvalidStatus);
+                            }
+                        }
+                        
+                        if let Some(appellant) = dispute.appellant {
+                            credit_juror(&mut state.juror_balances, appellant, dispute.appeal_bond);
+                        }
+                        dispute.appeal_bond = Amount::zero();
+                        if let Some(commitments) = state.commitments.get(&dispute_id) {
+                            for commitment in commitments.iter() {
+                                credit_juror(&mut state.juror_balances, commitment.voter, commitment.stake);
+                            }
+                        }
+                        state.commitments.insert(dispute_id, Vec::new());
+                    }
+                    _ => return Err(DisputeError::InvalidStatus),
+                }
+                
+                // The first verdict stands for lapsed appeals
+                let resolution = dispute.resolution.clone().ok_or(DisputeError::InvalidStatus)?;
+                dispute.status = DisputeStatus::Final;
+                (dispute.escrow_id, resolution)
+            };
+            
+            settle_escrow(host, escrow_id, dispute_id, &resolution)?;
+            
+            logger.log(&DisputeEvent::DisputeFinalized { dispute_id, resolution })?;
             
-            Ok(())
+            Ok(None)
         }
         
         UpdateParams::AddEvidence {
             dispute_id,
             evidence,
         } => {
-            let dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            let dispute = state.disputes.get(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
             
             // Only dispute parties can add evidence
-            let sender = ctx.sender();
+            let sender = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
             if sender != dispute.buyer && sender != dispute.seller {
                 return Err(DisputeError::Unauthorized);
             }
             
-            // Only allow evidence addition during open period
-            if dispute.status != DisputeStatus::Open {
+            // Only allow evidence addition before or while a round is open for commitments
+            if !matches!(
+                dispute.status,
+                DisputeStatus::PanelPending | DisputeStatus::Committing | DisputeStatus::Appealed
+            ) {
                 return Err(DisputeError::InvalidStatus);
             }
             
-            dispute.evidence.push(evidence.clone());
+            let now = ctx.metadata().slot_time();
+            if now > dispute.evidence_deadline {
+                return Err(DisputeError::EvidenceDeadlinePassed);
+            }
+            
+            let mut evidence_items = state.evidence.entry(dispute_id).or_insert(Vec::new());
+            let submitted = evidence_items.get_ref().iter().filter(|e| e.submitter == sender).count();
+            if submitted >= MAX_EVIDENCE_PER_PARTY {
+                return Err(DisputeError::EvidenceLimitReached);
+            }
+            
+            let item = evidence_item(sender, evidence, now)?;
+            let content_hash = item.content_hash;
+            let uri = item.uri.clone();
+            evidence_items.modify(|items| items.push(item));
             
             logger.log(&DisputeEvent::EvidenceAdded {
                 dispute_id,
                 added_by: sender,
-                evidence,
+                content_hash,
+                uri,
             })?;
             
-            Ok(())
+            Ok(None)
         }
         
         UpdateParams::CancelDispute { dispute_id } => {
@@ -483,45 +1296,136 @@ fn update(
                 return Err(DisputeError::Unauthorized);
             }
             
-            let dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
+            let mut dispute = state.disputes.get_mut(&dispute_id).ok_or(DisputeError::DisputeNotFound)?;
             
-            if dispute.status == DisputeStatus::Resolved {
+            if matches!(
+                dispute.status,
+                DisputeStatus::Resolved | DisputeStatus::Final | DisputeStatus::Cancelled
+            ) {
                 return Err(DisputeError::InvalidStatus);
             }
             
             dispute.status = DisputeStatus::Cancelled;
             
+            // Return a pending appeal bond and all juror stakes in full, exactly once
+            if let Some(appellant) = dispute.appellant {
+                credit_juror(&mut state.juror_balances, appellant, dispute.appeal_bond);
+            }
+            dispute.appeal_bond = Amount::zero();
+            if let Some(commitments) = state.commitments.get(&dispute_id) {
+                for commitment in commitments.iter() {
+                    credit_juror(&mut state.juror_balances, commitment.voter, commitment.stake);
+                }
+            }
+            state.commitments.insert(dispute_id, Vec::new());
+            
             logger.log(&DisputeEvent::DisputeCancelled { dispute_id })?;
             
-            Ok(())
+            Ok(None)
         }
         
         UpdateParams::UpdateParameters {
             min_reputation_to_vote,
             voting_period_hours,
+            reveal_period_hours,
+            evidence_cutoff_hours,
             min_votes_required,
+            panel_size,
             quorum_percentage,
+            weighting_curve,
+            min_juror_stake,
+            slash_percentage,
+            appeal_window_hours,
+            appeal_bond,
+            base_juror_reward,
         } => {
             if ctx.sender() != state.admin {
                 return Err(DisputeError::Unauthorized);
             }
             
+            // A panel must be able to reach the minimum number of votes
+            let new_panel_size = panel_size.unwrap_or(state.panel_size);
+            let new_min_votes = min_votes_required.unwrap_or(state.min_votes_required);
+            if new_panel_size == 0 || new_panel_size < new_min_votes {
+                return Err(DisputeError::InvalidPanelSize);
+            }
+            
             if let Some(min_rep) = min_reputation_to_vote {
                 state.min_reputation_to_vote = min_rep;
             }
             if let Some(period) = voting_period_hours {
                 state.voting_period_hours = period;
             }
+            if let Some(period) = reveal_period_hours {
+                state.reveal_period_hours = period;
+            }
+            if let Some(cutoff) = evidence_cutoff_hours {
+                state.evidence_cutoff_hours = cutoff;
+            }
             if let Some(min_votes) = min_votes_required {
                 state.min_votes_required = min_votes;
             }
+            if let Some(size) = panel_size {
+                state.panel_size = size;
+            }
             if let Some(quorum) = quorum_percentage {
                 if quorum <= 100 {
                     state.quorum_percentage = quorum;
                 }
             }
+            if let Some(curve) = weighting_curve {
+                state.weighting_curve = curve;
+            }
+            if let Some(stake) = min_juror_stake {
+                state.min_juror_stake = stake;
+            }
+            if let Some(percentage) = slash_percentage {
+                if percentage <= 100 {
+                    state.slash_percentage = percentage;
+                }
+            }
+            if let Some(window) = appeal_window_hours {
+                state.appeal_window_hours = window;
+            }
+            if let Some(bond) = appeal_bond {
+                state.appeal_bond = bond;
+            }
+            if let Some(reward) = base_juror_reward {
+                state.base_juror_reward = reward;
+            }
             
-            Ok(())
+            Ok(None)
+        }
+        
+        UpdateParams::FundRewardPool => {
+            state.reward_pool += amount;
+            
+            logger.log(&DisputeEvent::RewardPoolFunded {
+                amount,
+                reward_pool: state.reward_pool,
+            })?;
+            
+            Ok(None)
+        }
+        
+        UpdateParams::WithdrawJurorBalance { amount } => {
+            let juror = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(DisputeError::Unauthorized),
+            };
+            
+            let mut balance = state.juror_balances.get_mut(&juror).ok_or(DisputeError::InsufficientBalance)?;
+            if amount > *balance {
+                return Err(DisputeError::InsufficientBalance);
+            }
+            *balance -= amount;
+            drop(balance);
+            
+            host.invoke_transfer(&juror, amount).map_err(|_| DisputeError::TransferError)?;
+            
+            logger.log(&DisputeEvent::JurorBalanceWithdrawn { juror, amount })?;
+            
+            Ok(None)
         }
         
         UpdateParams::SetContractAddresses {
@@ -539,7 +1443,7 @@ fn update(
                 state.reputation_contract = Some(contract);
             }
             
-            Ok(())
+            Ok(None)
         }
     }
 }
@@ -557,7 +1461,7 @@ fn get_dispute(
     host: &Host<DisputeResolutionState>,
 ) -> ContractResult<Option<DisputeDetails>> {
     let dispute_id: DisputeId = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
-    Ok(host.state().disputes.get(&dispute_id).cloned())
+    Ok(host.state().disputes.get(&dispute_id).map(|dispute| dispute.clone()))
 }
 
 /// Get votes for a dispute
@@ -573,26 +1477,288 @@ fn get_votes(
     host: &Host<DisputeResolutionState>,
 ) -> ContractResult<Vec<Vote>> {
     let dispute_id: DisputeId = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
-    Ok(host.state().votes.get(&dispute_id).cloned().unwrap_or_default())
+    Ok(host.state().votes.get(&dispute_id).map(|votes| votes.clone()).unwrap_or_default())
 }
 
-// Helper functions
+/// Get the evidence submitted for a dispute
+#[receive(
+    contract = "credify_dispute",
+    name = "get_evidence",
+    parameter = "DisputeId",
+    return_value = "Vec<EvidenceItem>",
+    error = "DisputeError"
+)]
+fn get_evidence(
+    ctx: &ReceiveContext,
+    host: &Host<DisputeResolutionState>,
+) -> ContractResult<Vec<EvidenceItem>> {
+    let dispute_id: DisputeId = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
+    Ok(host.state().evidence.get(&dispute_id).map(|evidence| evidence.clone()).unwrap_or_default())
+}
+
+/// Get the juror panel drawn for a dispute
+#[receive(
+    contract = "credify_dispute",
+    name = "get_panel",
+    parameter = "DisputeId",
+    return_value = "Vec<AccountAddress>",
+    error = "DisputeError"
+)]
+fn get_panel(
+    ctx: &ReceiveContext,
+    host: &Host<DisputeResolutionState>,
+) -> ContractResult<Vec<AccountAddress>> {
+    let dispute_id: DisputeId = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
+    Ok(host.state().panels.get(&dispute_id).map(|panel| panel.clone()).unwrap_or_default())
+}
 
-/// Get voter's reputation (simplified implementation)
-fn get_voter_reputation<S>(
+/// Get a juror's withdrawable balance
+#[receive(
+    contract = "credify_dispute",
+    name = "get_juror_balance",
+    parameter = "AccountAddress",
+    return_value = "Amount",
+    error = "DisputeError"
+)]
+fn get_juror_balance(
+    ctx: &ReceiveContext,
+    host: &Host<DisputeResolutionState>,
+) -> ContractResult<Amount> {
+    let juror: AccountAddress = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
+    Ok(host.state().juror_balances.get(&juror).map(|b| *b).unwrap_or(Amount::zero()))
+}
+
+/// Compute the commitment for a vote choice and salt
+#[receive(
+    contract = "credify_dispute",
+    name = "get_vote_commitment",
+    parameter = "VoteCommitmentQuery",
+    return_value = "HashSha2256",
+    error = "DisputeError",
+    crypto_primitives
+)]
+fn get_vote_commitment(
+    ctx: &ReceiveContext,
     _host: &Host<DisputeResolutionState>,
-    _voter: AccountAddress,
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<HashSha2256> {
+    let query: VoteCommitmentQuery = ctx.parameter_cursor().get().map_err(|_| DisputeError::ParseError)?;
+    Ok(vote_commitment(crypto_primitives, query.dispute_id, query.voter, &query.choice, &query.salt))
+}
+
+// Helper functions
+
+/// Validate an evidence submission and turn it into an evidence record
+fn evidence_item(
+    submitter: AccountAddress,
+    submission: EvidenceSubmission,
+    submitted_at: Timestamp,
+) -> ContractResult<EvidenceItem> {
+    if submission.uri.len() > MAX_EVIDENCE_URI_LENGTH || submission.mime_type.len() > MAX_EVIDENCE_MIME_TYPE_LENGTH {
+        return Err(DisputeError::EvidenceTooLarge);
+    }
+    Ok(EvidenceItem {
+        submitter,
+        content_hash: submission.content_hash,
+        uri: submission.uri,
+        mime_type: submission.mime_type,
+        submitted_at,
+    })
+}
+
+/// Evidence closes `cutoff_hours` before the commit deadline, but never before the round starts
+fn evidence_deadline(round_start: Timestamp, voting_deadline: Timestamp, cutoff_hours: u64) -> Timestamp {
+    voting_deadline
+        .checked_sub(Duration::from_hours(cutoff_hours))
+        .map_or(round_start, |deadline| deadline.max(round_start))
+}
+
+/// Panel seed from the revealed seed and a timestamp fixed at commit time
+fn panel_seed(
+    crypto_primitives: &CryptoPrimitives,
+    seed: &[u8; 32],
+    seed_time: Timestamp,
+    dispute_id: DisputeId,
+) -> HashSha2256 {
+    let mut data = seed.to_vec();
+    data.extend_from_slice(&seed_time.timestamp_millis().to_be_bytes());
+    data.extend_from_slice(&dispute_id.to_be_bytes());
+    crypto_primitives.hash_sha2_256(&data)
+}
+
+/// Sample up to `MAX_PANEL_CANDIDATES` distinct jurors from the pool, excluding the parties
+///
+/// Small pools are taken whole. Larger pools are sampled by random index, so the cost of a draw
+/// does not grow with the number of registered jurors.
+fn sample_candidates(
+    crypto_primitives: &CryptoPrimitives,
+    juror_pool: &StateMap<u32, AccountAddress, StateApi>,
+    jurors: &StateMap<AccountAddress, JurorInfo, StateApi>,
+    pool_size: u32,
+    seed: &HashSha2256,
+    buyer: AccountAddress,
+    seller: AccountAddress,
+) -> Vec<(AccountAddress, u64)> {
+    let mut indices: Vec<u32> = Vec::new();
+    if pool_size <= MAX_PANEL_CANDIDATES {
+        indices.extend(0..pool_size);
+    } else {
+        // Duplicate indices are skipped, with a bounded number of attempts
+        for attempt in 0..MAX_PANEL_CANDIDATES * 2 {
+            if indices.len() >= MAX_PANEL_CANDIDATES as usize {
+                break;
+            }
+            let mut data = seed.0.to_vec();
+            data.extend_from_slice(b"pool");
+            data.extend_from_slice(&attempt.to_be_bytes());
+            let hash = crypto_primitives.hash_sha2_256(&data);
+            let mut random_bytes = [0u8; 4];
+            random_bytes.copy_from_slice(&hash.0[..4]);
+            let index = u32::from_be_bytes(random_bytes) % pool_size;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+    }
+    
+    indices
+        .into_iter()
+        .filter_map(|index| {
+            let juror = *juror_pool.get(&index)?;
+            if juror == buyer || juror == seller {
+                return None;
+            }
+            let info = jurors.get(&juror)?;
+            Some((juror, info.reputation_snapshot.max(1)))
+        })
+        .collect()
+}
+
+/// Draw up to `panel_size` distinct jurors, each draw weighted by reputation snapshot
+fn draw_panel(
+    crypto_primitives: &CryptoPrimitives,
+    mut candidates: Vec<(AccountAddress, u64)>,
+    panel_size: u32,
+    seed: &HashSha2256,
+) -> Vec<AccountAddress> {
+    let mut panel = Vec::new();
+    let mut draw: u32 = 0;
+    while panel.len() < panel_size as usize && !candidates.is_empty() {
+        let total_weight: u64 = candidates.iter().map(|(_, weight)| *weight).sum();
+        
+        let mut data = seed.0.to_vec();
+        data.extend_from_slice(&draw.to_be_bytes());
+        let hash = crypto_primitives.hash_sha2_256(&data);
+        let mut random_bytes = [0u8; 8];
+        random_bytes.copy_from_slice(&hash.0[..8]);
+        let mut target = u64::from_be_bytes(random_bytes) % total_weight;
+        
+        let index = candidates
+            .iter()
+            .position(|(_, weight)| {
+                if target < *weight {
+                    return true;
+                }
+                target -= *weight;
+                false
+            })
+            .unwrap_or(candidates.len() - 1);
+        panel.push(candidates.swap_remove(index).0);
+        draw += 1;
+    }
+    panel
+}
+
+/// SHA-256 of the dispute ID, voter, serialized vote choice and salt
+fn vote_commitment(
+    crypto_primitives: &CryptoPrimitives,
+    dispute_id: DisputeId,
+    voter: AccountAddress,
+    choice: &VoteChoice,
+    salt: &[u8; 32],
+) -> HashSha2256 {
+    let mut data = dispute_id.to_be_bytes().to_vec();
+    data.extend_from_slice(&voter.0);
+    data.extend_from_slice(&to_bytes(choice));
+    data.extend_from_slice(salt);
+    crypto_primitives.hash_sha2_256(&data)
+}
+
+/// Get voter's reputation score from the reputation contract
+fn get_voter_reputation(
+    host: &Host<DisputeResolutionState>,
+    voter: AccountAddress,
 ) -> ContractResult<u64> {
-    // In a real implementation, this would call the reputation contract
-    // For MVP, we'll return a fixed value for demonstration
-    Ok(100) // Assume all voters have sufficient reputation
+    let reputation_contract = host.state().reputation_contract.ok_or(DisputeError::ContractNotSet)?;
+    
+    let return_value = host
+        .invoke_contract_read_only(
+            &reputation_contract,
+            &voter,
+            EntrypointName::new_unchecked("get_score"),
+            Amount::zero(),
+        )
+        .map_err(|_| DisputeError::InvokeContractError)?;
+    
+    return_value
+        .ok_or(DisputeError::InvokeContractError)?
+        .get()
+        .map_err(|_| DisputeError::InvokeContractError)
+}
+
+/// Get voter's reputation score at a point in time from the reputation contract
+fn get_voter_reputation_at(
+    host: &Host<DisputeResolutionState>,
+    voter: AccountAddress,
+    timestamp: Timestamp,
+) -> ContractResult<u64> {
+    let reputation_contract = host.state().reputation_contract.ok_or(DisputeError::ContractNotSet)?;
+    
+    let query = ScoreAtQuery {
+        account: voter,
+        timestamp,
+    };
+    let return_value = host
+        .invoke_contract_read_only(
+            &reputation_contract,
+            &query,
+            EntrypointName::new_unchecked("get_score_at"),
+            Amount::zero(),
+        )
+        .map_err(|_| DisputeError::InvokeContractError)?;
+    
+    return_value
+        .ok_or(DisputeError::InvokeContractError)?
+        .get()
+        .map_err(|_| DisputeError::InvokeContractError)
 }
 
 /// Calculate vote weight based on reputation
-fn calculate_vote_weight(reputation: u64) -> u64 {
-    // Square root scaling to prevent excessive influence of high-reputation accounts
-    let base_weight = (reputation as f64).sqrt() as u64;
-    base_weight.max(1).min(100) // Min 1, max 100 weight
+///
+/// Uses integer arithmetic only, so weights can be reproduced exactly off-chain.
+fn calculate_vote_weight(reputation: u64, curve: &WeightingCurve) -> u64 {
+    let base_weight = match curve {
+        WeightingCurve::Linear => reputation / 100,
+        // Square root scaling to prevent excessive influence of high-reputation accounts
+        WeightingCurve::Sqrt => isqrt(reputation),
+        WeightingCurve::LogBucket => u64::from(u64::BITS - reputation.leading_zeros()),
+    };
+    base_weight.clamp(1, 100) // Min 1, max 100 weight
+}
+
+/// Integer square root (rounded down) using Newton's method
+fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    
+    let mut x = n;
+    let mut y = n / 2 + n % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
 }
 
 /// Calculate the final resolution based on weighted votes
@@ -642,17 +1808,154 @@ fn calculate_resolution(votes: &[Vote], quorum_percentage: u8) -> ContractResult
     })
 }
 
-/// Distribute rewards to voters for participation
-fn distribute_voting_rewards<S>(
-    _host: &Host<DisputeResolutionState>,
-    _dispute_id: DisputeId,
-    _votes: &[Vote],
+/// Call the escrow contract to settle a final resolution
+fn settle_escrow(
+    host: &mut Host<DisputeResolutionState>,
+    escrow_id: u64,
+    dispute_id: DisputeId,
+    resolution: &DisputeResolution,
 ) -> ContractResult<()> {
-    // In a real implementation, this would:
-    // 1. Calculate rewards based on vote correctness and participation
-    // 2. Update reputation scores for voters
-    // 3. Possibly distribute small CCD rewards
-    
-    // For MVP, we'll just acknowledge the participation
+    if let Some(escrow_contract) = host.state().escrow_contract {
+        let resolve_dispute = EscrowUpdateParams::ResolveDispute {
+            escrow_id,
+            dispute_id,
+            resolution: resolution.clone(),
+        };
+        host.invoke_contract(
+            &escrow_contract,
+            &resolve_dispute,
+            EntrypointName::new_unchecked("update"),
+            Amount::zero(),
+        )
+        .map_err(|_| DisputeError::InvokeContractError)?;
+    }
     Ok(())
+}
+
+/// Return juror stakes, slash jurors who voted against a clear majority or did not reveal,
+/// and pay jurors who voted with the resolution a base reward from the reward pool plus
+/// a share of the slashed stakes (and any forfeited appeal bond) by vote weight
+///
+/// Returns for every committed juror whether they voted with the resolution.
+fn distribute_voting_rewards(
+    host: &mut Host<DisputeResolutionState>,
+    logger: &mut Logger,
+    dispute_id: DisputeId,
+    resolution: &DisputeResolution,
+    votes: &[Vote],
+    forfeited_bond: Amount,
+) -> ContractResult<Vec<JurorOutcome>> {
+    let state = host.state_mut();
+    let commitments = state.commitments.get(&dispute_id).map(|c| c.clone()).unwrap_or_default();
+    // A split outcome means no side reached the quorum
+    let clear_majority = !matches!(resolution, DisputeResolution::Split { .. });
+    
+    let mut slashed_total = forfeited_bond;
+    let mut winning_weight = 0u64;
+    let mut outcomes = Vec::with_capacity(commitments.len());
+    for commitment in commitments.iter() {
+        let vote = votes.iter().find(|v| v.voter == commitment.voter);
+        let voted_with_resolution = vote.map_or(false, |v| votes_with_resolution(&v.choice, resolution));
+        outcomes.push(JurorOutcome {
+            juror: commitment.voter,
+            voted_with_resolution,
+        });
+        
+        let slash = match vote {
+            None => true,
+            Some(vote) if voted_with_resolution => {
+                winning_weight += vote.weight;
+                false
+            }
+            Some(_) => clear_majority,
+        };
+        
+        let mut returned = commitment.stake;
+        if slash {
+            let slashed = Amount::from_micro_ccd(
+                (commitment.stake.micro_ccd() * u64::from(state.slash_percentage)) / 100
+            );
+            slashed_total += slashed;
+            returned -= slashed;
+            
+            logger.log(&DisputeEvent::StakeSlashed {
+                dispute_id,
+                voter: commitment.voter,
+                amount: slashed,
+            })?;
+        }
+        credit_juror(&mut state.juror_balances, commitment.voter, returned);
+    }
+    
+    // Jurors voting with the resolution get a base reward while the pool lasts,
+    // plus a share of slashed stakes by vote weight
+    let mut distributed = Amount::zero();
+    for vote in votes.iter().filter(|v| votes_with_resolution(&v.choice, resolution)) {
+        let base_reward = state.base_juror_reward.min(state.reward_pool);
+        state.reward_pool -= base_reward;
+        let share = if winning_weight > 0 {
+            Amount::from_micro_ccd((slashed_total.micro_ccd() * vote.weight) / winning_weight)
+        } else {
+            Amount::zero()
+        };
+        distributed += share;
+        
+        let reward = base_reward + share;
+        if reward == Amount::zero() {
+            continue;
+        }
+        credit_juror(&mut state.juror_balances, vote.voter, reward);
+        
+        logger.log(&DisputeEvent::JurorRewarded {
+            dispute_id,
+            voter: vote.voter,
+            reward,
+        })?;
+    }
+    
+    // Rounding remainders and slashes without winning jurors go to the admin
+    let remainder = slashed_total - distributed;
+    let admin = state.admin;
+    credit_juror(&mut state.juror_balances, admin, remainder);
+    
+    Ok(outcomes)
+}
+
+/// Report juror outcomes to the reputation contract's juror sub-scores
+///
+/// Best effort: a failing reputation contract must not block dispute resolution.
+fn report_juror_outcomes(host: &mut Host<DisputeResolutionState>, outcomes: Vec<JurorOutcome>) {
+    let reputation_contract = match host.state().reputation_contract {
+        Some(contract) => contract,
+        None => return,
+    };
+    if outcomes.is_empty() {
+        return;
+    }
+    
+    let update = ReputationUpdateParams::UpdateFromJurors { outcomes };
+    let _ = host.invoke_contract(
+        &reputation_contract,
+        &update,
+        EntrypointName::new_unchecked("update"),
+        Amount::zero(),
+    );
+}
+
+/// Whether a vote choice agrees with the final resolution
+fn votes_with_resolution(choice: &VoteChoice, resolution: &DisputeResolution) -> bool {
+    matches!(
+        (choice, resolution),
+        (VoteChoice::FavorBuyer, DisputeResolution::FavorBuyer)
+            | (VoteChoice::FavorSeller, DisputeResolution::FavorSeller)
+            | (VoteChoice::Split { .. }, DisputeResolution::Split { .. })
+    )
+}
+
+/// Add an amount to a juror's withdrawable balance
+fn credit_juror(balances: &mut StateMap<AccountAddress, Amount, StateApi>, juror: AccountAddress, amount: Amount) {
+    if amount == Amount::zero() {
+        return;
+    }
+    balances.entry(juror).or_insert(Amount::zero()).modify(|balance| *balance += amount);
 }
\ No newline at end of file
@@ -8,28 +8,68 @@ Features:
 - Identity verification requirements
 - Reputation system integration
 - Multi-stage transaction lifecycle
+- Milestone-based escrows with partial releases
+- CCD and CIS-2 token (stablecoin) escrows
 */
 
+use concordium_cis2::*;
 use concordium_std::*;
 
+use crate::dispute_resolution::{
+    DisputeId, DisputeResolution, EvidenceSubmission, UpdateParams as DisputeUpdateParams,
+};
+use crate::reputation::{ReputationUpdateParams, MAX_CATEGORY_LENGTH};
+use crate::verifier_registry::Attestation;
+
 // Contract state
-#[derive(Serialize, SchemaType)]
-pub struct EscrowState {
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct EscrowState<S: HasStateApi = StateApi> {
     /// Map of escrow ID to escrow details
-    pub escrows: collections::BTreeMap<EscrowId, EscrowDetails>,
+    pub escrows: StateMap<EscrowId, EscrowDetails, S>,
     /// Admin account that can manage the contract
     pub admin: AccountAddress,
     /// Platform fee percentage (basis points, e.g., 200 = 2%)
     pub platform_fee: u32,
+    /// Account receiving withdrawn platform fees
+    pub fee_recipient: AccountAddress,
+    /// Total platform fees collected by the contract
+    pub accrued_fees: Amount,
+    /// Total platform fees withdrawn from the contract
+    pub withdrawn_fees: Amount,
+    /// CCD held for escrows that have not been released or refunded yet
+    pub locked_balance: Amount,
     /// Dispute resolution contract address
     pub dispute_contract: Option<ContractAddress>,
+    /// Reputation contract address
+    pub reputation_contract: Option<ContractAddress>,
     /// Next escrow ID to use
     pub next_escrow_id: EscrowId,
+    /// Default time the buyer has to complete a transaction after delivery (in hours)
+    pub confirmation_window_hours: u64,
+    /// CIS-2 token contracts accepted for token escrows
+    pub accepted_tokens: StateSet<ContractAddress, S>,
+    /// Verifier registry contract deciding which verifiers are trusted
+    pub verifier_registry: Option<ContractAddress>,
+    /// Attributes parties must prove for escrows requiring identity verification
+    pub identity_requirements: IdentityRequirements,
 }
 
 // Escrow identifier
 pub type EscrowId = u64;
 
+// High bit marking ids derived from an order reference, keeping them apart from sequential ids
+const ORDER_REF_ID_FLAG: EscrowId = 1 << 63;
+// Maximum length of an order reference in bytes
+const MAX_ORDER_REF_LENGTH: usize = 64;
+
+// Lookup of an escrow by the buyer's order reference
+#[derive(Serialize, SchemaType)]
+pub struct EscrowRefQuery {
+    pub buyer: AccountAddress,
+    pub order_ref: String,
+}
+
 // Escrow details
 #[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
 pub struct EscrowDetails {
@@ -37,8 +77,10 @@ pub struct EscrowDetails {
     pub buyer: AccountAddress,
     /// Seller account
     pub seller: AccountAddress,
-    /// Amount in escrow (in microCCD)
+    /// Amount in escrow (in microCCD, or in token units for CIS-2 escrows)
     pub amount: Amount,
+    /// CIS-2 token held in escrow (None for CCD escrows)
+    pub token: Option<EscrowToken>,
     /// Product/service description
     pub description: String,
     /// Current status of the escrow
@@ -54,7 +96,117 @@ pub struct EscrowDetails {
     /// Identity verification status for seller
     pub seller_verified: bool,
     /// Dispute resolution ID if dispute was raised
-    pub dispute_id: Option<u64>,
+    pub dispute_id: Option<DisputeId>,
+    /// Per-escrow confirmation window overriding the contract default (in hours)
+    pub confirmation_window_hours: Option<u64>,
+    /// Timestamp after which payment can be released to the seller without buyer action
+    pub release_deadline: Option<Timestamp>,
+    /// Ordered milestones (empty for single-payment escrows)
+    pub milestones: Vec<Milestone>,
+    /// Milestone under dispute (milestone escrows only)
+    pub disputed_milestone: Option<MilestoneIndex>,
+    /// Backend order reference this escrow was funded for
+    pub order_ref: Option<String>,
+    /// Product category, tracked as a reputation sub-score
+    pub category: Option<String>,
+    /// Identity attributes proven by the buyer
+    pub buyer_identity: Option<VerifiedIdentity>,
+    /// Identity attributes proven by the seller
+    pub seller_identity: Option<VerifiedIdentity>,
+}
+
+// Identity attributes proven to a trusted verifier
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq, Debug)]
+pub struct ProvenAttributes {
+    /// Age the holder is proven to be at least (0 if not proven)
+    pub age_over: u8,
+    /// Country of residence (ISO 3166-1 alpha-2), if proven
+    pub country: Option<String>,
+}
+
+// Attributes required for identity-verified escrows
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct IdentityRequirements {
+    /// Minimum proven age
+    pub min_age: u8,
+    /// Accepted countries of residence (any if empty)
+    pub allowed_countries: Vec<String>,
+}
+
+// Attestation signed by a trusted verifier after checking the holder's
+// Concordium identity credential (verifiable presentation) off-chain
+#[derive(Serialize, SchemaType)]
+pub struct IdentityAttestation {
+    /// Verifier account that signed the attestation
+    pub verifier: AccountAddress,
+    /// Attributes proven by the credential
+    pub attributes: ProvenAttributes,
+    /// Timestamp after which the attestation is no longer accepted
+    pub expires_at: Timestamp,
+    /// Verifier signature over the serialized `IdentityAttestationMessage`
+    pub signature: AccountSignatures,
+}
+
+// Message signed by the verifier
+#[derive(Serialize, SchemaType)]
+pub struct IdentityAttestationMessage {
+    /// Escrow contract the attestation is meant for
+    pub contract: ContractAddress,
+    /// Account whose identity was verified
+    pub subject: AccountAddress,
+    pub attributes: ProvenAttributes,
+    pub expires_at: Timestamp,
+}
+
+// Identity proven for an escrow party
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct VerifiedIdentity {
+    /// Verifier that attested the attributes
+    pub verifier: AccountAddress,
+    /// Proven attributes
+    pub attributes: ProvenAttributes,
+    /// Timestamp of confirmation
+    pub verified_at: Timestamp,
+}
+
+// Milestone index within an escrow
+pub type MilestoneIndex = u32;
+
+// Milestone of a milestone-based escrow
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct Milestone {
+    /// Deliverable for this milestone
+    pub description: String,
+    /// Amount released when this milestone is approved
+    pub amount: Amount,
+    /// Current status of the milestone
+    pub status: MilestoneStatus,
+    /// Timestamp after which the delivered milestone can be released without buyer action
+    pub release_deadline: Option<Timestamp>,
+}
+
+// Milestone status
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq, Debug)]
+pub enum MilestoneStatus {
+    /// Waiting for delivery
+    Pending,
+    /// Delivered, waiting for buyer approval
+    DeliveryConfirmed,
+    /// Dispute raised for this milestone
+    Disputed,
+    /// Payment released to seller
+    Released,
+    /// Amount refunded to buyer
+    Refunded,
+}
+
+// CIS-2 token held by a token escrow
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq, Debug)]
+pub struct EscrowToken {
+    /// CIS-2 token contract
+    pub contract: ContractAddress,
+    /// Token ID within the token contract
+    pub token_id: ContractTokenId,
 }
 
 // Escrow status
@@ -83,8 +235,48 @@ pub struct InitParams {
     pub admin: AccountAddress,
     /// Platform fee in basis points
     pub platform_fee: u32,
+    /// Account receiving withdrawn platform fees
+    pub fee_recipient: AccountAddress,
+    /// Default buyer confirmation window in hours
+    pub confirmation_window_hours: u64,
+}
+
+// Milestone definition when creating a milestone escrow
+#[derive(Serialize, SchemaType, Clone)]
+pub struct MilestoneTerms {
+    pub description: String,
+    pub amount: Amount,
+}
+
+// Escrow terms attached as additional data when funding an escrow with CIS-2 tokens
+#[derive(Serialize, SchemaType)]
+pub struct EscrowTerms {
+    pub seller: AccountAddress,
+    pub description: String,
+    pub refund_deadline_hours: Option<u64>,
+    pub requires_identity_verification: bool,
+    pub confirmation_window_hours: Option<u64>,
+    /// Milestones (empty for a single payment), amounts must add up to the funded amount
+    pub milestones: Vec<MilestoneTerms>,
+    /// Backend order reference
+    pub order_ref: Option<String>,
+    /// Product category
+    pub category: Option<String>,
 }
 
+// Cart entry for one vendor when checking out several escrows at once
+#[derive(Serialize, SchemaType)]
+pub struct CartItem {
+    pub seller: AccountAddress,
+    pub amount: Amount,
+    pub description: String,
+    pub order_ref: String,
+    pub category: Option<String>,
+}
+
+// Maximum number of escrows created by a single batch
+const MAX_ESCROW_BATCH_SIZE: usize = 50;
+
 // Contract update parameters
 #[derive(Serialize, SchemaType)]
 pub enum UpdateParams {
@@ -94,10 +286,46 @@ pub enum UpdateParams {
         description: String,
         refund_deadline_hours: Option<u64>,
         requires_identity_verification: bool,
+        confirmation_window_hours: Option<u64>,
+        /// Backend order reference the escrow id is derived from
+        order_ref: Option<String>,
+        /// Product category
+        category: Option<String>,
+    },
+    /// Create one escrow per vendor from a cart, funded by a single payment
+    CreateEscrowBatch {
+        items: Vec<CartItem>,
+        refund_deadline_hours: Option<u64>,
+        requires_identity_verification: bool,
+    },
+    /// Create a new escrow released in ordered milestones
+    CreateMilestoneEscrow {
+        seller: AccountAddress,
+        description: String,
+        milestones: Vec<MilestoneTerms>,
+        refund_deadline_hours: Option<u64>,
+        requires_identity_verification: bool,
+        category: Option<String>,
+    },
+    /// Confirm delivery of the next milestone (seller action)
+    ConfirmMilestoneDelivery {
+        escrow_id: EscrowId,
+        milestone_index: MilestoneIndex,
+    },
+    /// Approve a delivered milestone and release its payment (buyer action)
+    ApproveMilestone {
+        escrow_id: EscrowId,
+        milestone_index: MilestoneIndex,
+    },
+    /// Release a delivered milestone after the buyer confirmation window (anyone)
+    ReleaseMilestoneAfterTimeout {
+        escrow_id: EscrowId,
+        milestone_index: MilestoneIndex,
     },
     /// Confirm identity verification
     ConfirmIdentity {
         escrow_id: EscrowId,
+        attestation: IdentityAttestation,
     },
     /// Activate escrow (when all conditions are met)
     ActivateEscrow {
@@ -111,14 +339,29 @@ pub enum UpdateParams {
     CompleteTransaction {
         escrow_id: EscrowId,
     },
+    /// Claim refund after the refund deadline has passed (buyer action)
+    ClaimRefund {
+        escrow_id: EscrowId,
+    },
+    /// Release payment to seller after the buyer confirmation window (anyone)
+    ReleaseAfterTimeout {
+        escrow_id: EscrowId,
+    },
     /// Raise dispute
     RaiseDispute {
         escrow_id: EscrowId,
+        /// Disputed milestone (required for milestone escrows)
+        milestone_index: Option<MilestoneIndex>,
         reason: String,
+        evidence: Vec<EvidenceSubmission>,
+        /// SHA-256 of a secret seed, revealed to the dispute contract to draw the juror panel
+        seed_commit: HashSha2256,
     },
     /// Resolve dispute (called by dispute resolution contract)
     ResolveDispute {
         escrow_id: EscrowId,
+        /// Dispute the resolution belongs to
+        dispute_id: DisputeId,
         resolution: DisputeResolution,
     },
     /// Cancel escrow (admin only)
@@ -133,17 +376,36 @@ pub enum UpdateParams {
     SetDisputeContract {
         contract_address: ContractAddress,
     },
-}
-
-// Dispute resolution options
-#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
-pub enum DisputeResolution {
-    /// Favor buyer - full refund
-    FavorBuyer,
-    /// Favor seller - release payment
-    FavorSeller,
-    /// Split payment (percentage to seller)
-    Split { seller_percentage: u8 },
+    /// Set reputation contract (admin only)
+    SetReputationContract {
+        contract_address: ContractAddress,
+    },
+    /// Update default buyer confirmation window (admin only)
+    UpdateConfirmationWindow {
+        hours: u64,
+    },
+    /// Withdraw accrued platform fees (admin only, defaults to the fee recipient)
+    WithdrawFees {
+        to: Option<AccountAddress>,
+        amount: Amount,
+    },
+    /// Set the platform fee recipient (admin only)
+    SetFeeRecipient {
+        recipient: AccountAddress,
+    },
+    /// Accept or reject a CIS-2 token contract for token escrows (admin only)
+    SetAcceptedToken {
+        contract: ContractAddress,
+        accepted: bool,
+    },
+    /// Set verifier registry contract (admin only)
+    SetVerifierRegistry {
+        contract_address: ContractAddress,
+    },
+    /// Set attributes required for identity verification (admin only)
+    SetIdentityRequirements {
+        requirements: IdentityRequirements,
+    },
 }
 
 // Contract errors
@@ -163,10 +425,40 @@ pub enum EscrowError {
     InvalidDisputeResolution,
     /// Refund deadline not reached
     RefundDeadlineNotReached,
+    /// Buyer confirmation window has not elapsed
+    ConfirmationWindowNotElapsed,
     /// Parse error
     ParseError,
     /// Contract invocation error
     InvokeContractError,
+    /// Dispute resolution contract not set
+    DisputeContractNotSet,
+    /// Verifier registry contract not set
+    VerifierRegistryNotSet,
+    /// Milestone not found or not next in order
+    InvalidMilestone,
+    /// Milestone amounts do not add up to the escrowed amount
+    MilestoneAmountMismatch,
+    /// Batch is empty, too large or contains a zero amount
+    InvalidBatch,
+    /// Batch amounts do not add up to the attached amount
+    BatchAmountMismatch,
+    /// An escrow already exists for this order reference
+    DuplicateOrderRef,
+    /// Product category name is too long
+    InvalidCategory,
+    /// Order reference is too long
+    InvalidOrderRef,
+    /// Token contract is not accepted for escrows
+    TokenNotAccepted,
+    /// Attestation signed by an untrusted verifier
+    UntrustedVerifier,
+    /// Attestation has expired
+    AttestationExpired,
+    /// Attestation signature is invalid
+    InvalidAttestation,
+    /// Proven attributes do not meet the identity requirements
+    IdentityRequirementsNotMet,
     /// Transfer error
     TransferError,
 }
@@ -180,6 +472,15 @@ pub enum EscrowEvent {
         buyer: AccountAddress,
         seller: AccountAddress,
         amount: Amount,
+        token: Option<EscrowToken>,
+        order_ref: Option<String>,
+    },
+    /// Party identity confirmed by a trusted verifier
+    IdentityConfirmed {
+        escrow_id: EscrowId,
+        account: AccountAddress,
+        verifier: AccountAddress,
+        attributes: ProvenAttributes,
     },
     /// Escrow activated
     EscrowActivated {
@@ -195,10 +496,23 @@ pub enum EscrowEvent {
         seller_amount: Amount,
         platform_fee: Amount,
     },
+    /// Milestone delivery confirmed
+    MilestoneDeliveryConfirmed {
+        escrow_id: EscrowId,
+        milestone_index: MilestoneIndex,
+    },
+    /// Milestone approved and paid out
+    MilestoneApproved {
+        escrow_id: EscrowId,
+        milestone_index: MilestoneIndex,
+        seller_amount: Amount,
+        platform_fee: Amount,
+    },
     /// Dispute raised
     DisputeRaised {
         escrow_id: EscrowId,
-        dispute_id: u64,
+        milestone_index: Option<MilestoneIndex>,
+        dispute_id: DisputeId,
     },
     /// Dispute resolved
     DisputeResolved {
@@ -210,21 +524,52 @@ pub enum EscrowEvent {
         escrow_id: EscrowId,
         refund_amount: Amount,
     },
+    /// Platform fees withdrawn
+    FeesWithdrawn {
+        to: AccountAddress,
+        amount: Amount,
+    },
+}
+
+// Platform fee accounting
+#[derive(Serialize, SchemaType)]
+pub struct FeeInfo {
+    /// Account receiving withdrawn platform fees
+    pub fee_recipient: AccountAddress,
+    /// Total CCD platform fees collected
+    pub accrued_fees: Amount,
+    /// Total CCD platform fees withdrawn
+    pub withdrawn_fees: Amount,
 }
 
 type ContractResult<T> = Result<T, EscrowError>;
+type ContractTokenId = TokenIdVec;
+type ContractTokenAmount = TokenAmountU64;
 
 /// Initialize the escrow contract
 #[init(contract = "credify_escrow", parameter = "InitParams")]
-fn init(ctx: &InitContext, _state_builder: &mut StateBuilder) -> InitResult<EscrowState> {
+fn init(ctx: &InitContext, state_builder: &mut StateBuilder) -> InitResult<EscrowState> {
     let params: InitParams = ctx.parameter_cursor().get()?;
     
     let state = EscrowState {
-        escrows: collections::BTreeMap::new(),
+        escrows: state_builder.new_map(),
         admin: params.admin,
         platform_fee: params.platform_fee,
+        fee_recipient: params.fee_recipient,
+        accrued_fees: Amount::zero(),
+        withdrawn_fees: Amount::zero(),
+        locked_balance: Amount::zero(),
         dispute_contract: None,
+        reputation_contract: None,
         next_escrow_id: 0,
+        confirmation_window_hours: params.confirmation_window_hours,
+        accepted_tokens: state_builder.new_set(),
+        verifier_registry: None,
+        // Parties must prove they are adults until the admin configures otherwise
+        identity_requirements: IdentityRequirements {
+            min_age: 18,
+            allowed_countries: Vec::new(),
+        },
     };
     
     Ok(state)
@@ -237,12 +582,14 @@ fn init(ctx: &InitContext, _state_builder: &mut StateBuilder) -> InitResult<Escr
     parameter = "UpdateParams",
     error = "EscrowError",
     enable_logger,
-    mutable
+    mutable,
+    crypto_primitives
 )]
 fn update(
     ctx: &ReceiveContext,
     host: &mut Host<EscrowState>,
     logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
 ) -> ContractResult<()> {
     let params: UpdateParams = ctx.parameter_cursor().get().map_err(|_| EscrowError::ParseError)?;
     let state = host.state_mut();
@@ -253,6 +600,9 @@ fn update(
             description,
             refund_deadline_hours,
             requires_identity_verification,
+            confirmation_window_hours,
+            order_ref,
+            category,
         } => {
             // Verify the amount sent with the transaction
             let amount = ctx.amount();
@@ -260,57 +610,267 @@ fn update(
                 return Err(EscrowError::InsufficientFunds);
             }
             
-            // Calculate refund deadline if specified
-            let refund_deadline = refund_deadline_hours.map(|hours| {
-                ctx.metadata().slot_time().add_duration(Duration::from_hours(hours))
-            });
+            let terms = EscrowTerms {
+                seller,
+                description,
+                refund_deadline_hours,
+                requires_identity_verification,
+                confirmation_window_hours,
+                milestones: Vec::new(),
+                order_ref,
+                category,
+            };
+            create_escrow(state, logger, crypto_primitives, ctx.sender(), amount, None, terms, ctx.metadata().slot_time())?;
             
-            // Create new escrow
-            let escrow_id = state.next_escrow_id;
-            let escrow = EscrowDetails {
-                buyer: ctx.sender(),
+            Ok(())
+        }
+        
+        UpdateParams::CreateEscrowBatch {
+            items,
+            refund_deadline_hours,
+            requires_identity_verification,
+        } => {
+            if items.is_empty() || items.len() > MAX_ESCROW_BATCH_SIZE {
+                return Err(EscrowError::InvalidBatch);
+            }
+            
+            // Every entry must be funded and the entries must add up to the attached amount
+            let mut total = Amount::zero();
+            for item in items.iter() {
+                if item.amount == Amount::zero() {
+                    return Err(EscrowError::InvalidBatch);
+                }
+                total = total.checked_add(item.amount).ok_or(EscrowError::BatchAmountMismatch)?;
+            }
+            if total != ctx.amount() {
+                return Err(EscrowError::BatchAmountMismatch);
+            }
+            
+            // Any failure below rejects the whole transaction, so no partial batch is stored
+            let now = ctx.metadata().slot_time();
+            for item in items {
+                let terms = EscrowTerms {
+                    seller: item.seller,
+                    description: item.description,
+                    refund_deadline_hours,
+                    requires_identity_verification,
+                    confirmation_window_hours: None,
+                    milestones: Vec::new(),
+                    order_ref: Some(item.order_ref),
+                    category: item.category,
+                };
+                create_escrow(state, logger, crypto_primitives, ctx.sender(), item.amount, None, terms, now)?;
+            }
+            
+            Ok(())
+        }
+        
+        UpdateParams::CreateMilestoneEscrow {
+            seller,
+            description,
+            milestones,
+            refund_deadline_hours,
+            requires_identity_verification,
+            category,
+        } => {
+            // Verify the amount sent with the transaction
+            let amount = ctx.amount();
+            if amount == Amount::zero() {
+                return Err(EscrowError::InsufficientFunds);
+            }
+            
+            if milestones.is_empty() {
+                return Err(EscrowError::InvalidMilestone);
+            }
+            
+            let terms = EscrowTerms {
                 seller,
-                amount,
-                description: description.clone(),
-                status: EscrowStatus::Created,
-                created_at: ctx.metadata().slot_time(),
-                refund_deadline,
+                description,
+                refund_deadline_hours,
                 requires_identity_verification,
-                buyer_verified: !requires_identity_verification, // If no verification required, mark as verified
-                seller_verified: !requires_identity_verification,
-                dispute_id: None,
+                confirmation_window_hours: None,
+                milestones,
+                order_ref: None,
+                category,
             };
+            create_escrow(state, logger, crypto_primitives, ctx.sender(), amount, None, terms, ctx.metadata().slot_time())?;
             
-            state.escrows.insert(escrow_id, escrow);
-            state.next_escrow_id += 1;
+            Ok(())
+        }
+        
+        UpdateParams::ConfirmMilestoneDelivery {
+            escrow_id,
+            milestone_index,
+        } => {
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             
-            // Log event
-            logger.log(&EscrowEvent::EscrowCreated {
+            // Only seller can confirm delivery
+            if ctx.sender() != escrow.seller {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            if escrow.status != EscrowStatus::Active {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            // Milestones are delivered in order, a disputed milestone does not hold up later ones
+            let index = milestone_index as usize;
+            if index >= escrow.milestones.len()
+                || escrow.milestones[..index].iter().any(|m| m.status == MilestoneStatus::Pending)
+            {
+                return Err(EscrowError::InvalidMilestone);
+            }
+            
+            let window_hours = escrow.confirmation_window_hours.unwrap_or(state.confirmation_window_hours);
+            let milestone = &mut escrow.milestones[index];
+            if milestone.status != MilestoneStatus::Pending {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            milestone.status = MilestoneStatus::DeliveryConfirmed;
+            
+            // Start the buyer confirmation window for this milestone
+            milestone.release_deadline = ctx.metadata().slot_time().checked_add(Duration::from_hours(window_hours));
+            
+            logger.log(&EscrowEvent::MilestoneDeliveryConfirmed {
                 escrow_id,
-                buyer: ctx.sender(),
-                seller,
-                amount,
+                milestone_index,
+            })?;
+            
+            Ok(())
+        }
+        
+        UpdateParams::ApproveMilestone {
+            escrow_id,
+            milestone_index,
+        } => {
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            
+            // Only buyer can approve a milestone
+            if ctx.sender() != escrow.buyer {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            if escrow.status != EscrowStatus::Active {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            let milestone = escrow.milestones.get(milestone_index as usize).ok_or(EscrowError::InvalidMilestone)?;
+            if milestone.status != MilestoneStatus::DeliveryConfirmed {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            let (seller_amount, platform_fee_amount) =
+                release_milestone(host, ctx.self_address(), &mut escrow, milestone_index)?;
+            
+            logger.log(&EscrowEvent::MilestoneApproved {
+                escrow_id,
+                milestone_index,
+                seller_amount,
+                platform_fee: platform_fee_amount,
+            })?;
+            
+            // Credit both parties once the last milestone is settled
+            if escrow.status == EscrowStatus::Completed {
+                report_transaction(host, &escrow);
+            }
+            
+            Ok(())
+        }
+        
+        UpdateParams::ReleaseMilestoneAfterTimeout {
+            escrow_id,
+            milestone_index,
+        } => {
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            
+            if escrow.status != EscrowStatus::Active {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            // A dispute moves the milestone out of DeliveryConfirmed and blocks the release
+            let milestone = escrow.milestones.get(milestone_index as usize).ok_or(EscrowError::InvalidMilestone)?;
+            if milestone.status != MilestoneStatus::DeliveryConfirmed {
+                return Err(EscrowError::InvalidStatus);
+            }
+            match milestone.release_deadline {
+                Some(deadline) if ctx.metadata().slot_time() >= deadline => {}
+                _ => return Err(EscrowError::ConfirmationWindowNotElapsed),
+            }
+            
+            let (seller_amount, platform_fee_amount) =
+                release_milestone(host, ctx.self_address(), &mut escrow, milestone_index)?;
+            
+            logger.log(&EscrowEvent::MilestoneApproved {
+                escrow_id,
+                milestone_index,
+                seller_amount,
+                platform_fee: platform_fee_amount,
             })?;
             
+            if escrow.status == EscrowStatus::Completed {
+                report_transaction(host, &escrow);
+            }
+            
             Ok(())
         }
         
-        UpdateParams::ConfirmIdentity { escrow_id } => {
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+        UpdateParams::ConfirmIdentity {
+            escrow_id,
+            attestation,
+        } => {
+            let account = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(EscrowError::Unauthorized),
+            };
+            let now = ctx.metadata().slot_time();
             
             // Only buyer or seller can confirm their identity
-            if ctx.sender() == escrow.buyer {
+            let (buyer, seller) = host
+                .state()
+                .escrows
+                .get(&escrow_id)
+                .map(|escrow| (escrow.buyer, escrow.seller))
+                .ok_or(EscrowError::EscrowNotFound)?;
+            if account != buyer && account != seller {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            // The attestation must come from a trusted verifier and meet the requirements
+            verify_identity_attestation(host, ctx.self_address(), account, &attestation, now)?;
+            let identity = VerifiedIdentity {
+                verifier: attestation.verifier,
+                attributes: attestation.attributes,
+                verified_at: now,
+            };
+            
+            // The other party's registry attestation may have been revoked since they confirmed
+            let counterparty = if account == buyer { seller } else { buyer };
+            let counterparty_attested = registry_attestation(host, counterparty)?.is_some();
+            
+            let state = host.state_mut();
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            
+            if account == buyer {
                 escrow.buyer_verified = true;
-            } else if ctx.sender() == escrow.seller {
-                escrow.seller_verified = true;
+                escrow.buyer_identity = Some(identity.clone());
             } else {
-                return Err(EscrowError::Unauthorized);
+                escrow.seller_verified = true;
+                escrow.seller_identity = Some(identity.clone());
             }
             
+            logger.log(&EscrowEvent::IdentityConfirmed {
+                escrow_id,
+                account,
+                verifier: identity.verifier,
+                attributes: identity.attributes,
+            })?;
+            
             // Auto-activate if both parties are verified and escrow is still created
             if escrow.status == EscrowStatus::Created 
                 && escrow.buyer_verified 
-                && escrow.seller_verified {
+                && escrow.seller_verified
+                && (!escrow.requires_identity_verification || counterparty_attested) {
                 escrow.status = EscrowStatus::Active;
                 
                 logger.log(&EscrowEvent::EscrowActivated { escrow_id })?;
@@ -320,7 +880,18 @@ fn update(
         }
         
         UpdateParams::ActivateEscrow { escrow_id } => {
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            // Confirmed identities only count while the registry still attests both parties
+            let (buyer, seller, requires_identity_verification) = host
+                .state()
+                .escrows
+                .get(&escrow_id)
+                .map(|escrow| (escrow.buyer, escrow.seller, escrow.requires_identity_verification))
+                .ok_or(EscrowError::EscrowNotFound)?;
+            let parties_attested = !requires_identity_verification
+                || (registry_attestation(host, buyer)?.is_some() && registry_attestation(host, seller)?.is_some());
+            
+            let state = host.state_mut();
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             
             // Only buyer or admin can activate
             if ctx.sender() != escrow.buyer && ctx.sender() != state.admin {
@@ -332,7 +903,9 @@ fn update(
             }
             
             // Check if identity verification is complete
-            if escrow.requires_identity_verification && (!escrow.buyer_verified || !escrow.seller_verified) {
+            if escrow.requires_identity_verification
+                && (!escrow.buyer_verified || !escrow.seller_verified || !parties_attested)
+            {
                 return Err(EscrowError::IdentityVerificationRequired);
             }
             
@@ -344,26 +917,31 @@ fn update(
         }
         
         UpdateParams::ConfirmDelivery { escrow_id } => {
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             
             // Only seller can confirm delivery
             if ctx.sender() != escrow.seller {
                 return Err(EscrowError::Unauthorized);
             }
             
-            if escrow.status != EscrowStatus::Active {
+            // Milestone escrows are delivered milestone by milestone
+            if escrow.status != EscrowStatus::Active || !escrow.milestones.is_empty() {
                 return Err(EscrowError::InvalidStatus);
             }
             
             escrow.status = EscrowStatus::DeliveryConfirmed;
             
+            // Start the buyer confirmation window
+            let window_hours = escrow.confirmation_window_hours.unwrap_or(state.confirmation_window_hours);
+            escrow.release_deadline = ctx.metadata().slot_time().checked_add(Duration::from_hours(window_hours));
+            
             logger.log(&EscrowEvent::DeliveryConfirmed { escrow_id })?;
             
             Ok(())
         }
         
         UpdateParams::CompleteTransaction { escrow_id } => {
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             
             // Only buyer can complete the transaction
             if ctx.sender() != escrow.buyer {
@@ -375,21 +953,99 @@ fn update(
             }
             
             // Calculate platform fee and seller amount
-            let platform_fee_amount = Amount::from_micro_ccd(
-                (escrow.amount.micro_ccd() * u64::from(state.platform_fee)) / 10000
-            );
+            let platform_fee_amount = calculate_platform_fee(escrow.amount, state.platform_fee);
             let seller_amount = escrow.amount - platform_fee_amount;
             
             // Transfer to seller
             if seller_amount > Amount::zero() {
-                host.invoke_transfer(&escrow.seller, seller_amount)
-                    .map_err(|_| EscrowError::TransferError)?;
+                transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.seller, seller_amount)?;
             }
             
             // Keep platform fee in contract (admin can withdraw later)
+            collect_platform_fee(host, ctx.self_address(), &escrow, platform_fee_amount)?;
+            unlock_funds(host, &escrow, escrow.amount);
+            
+            escrow.status = EscrowStatus::Completed;
+            
+            // Credit both parties for the successful transaction
+            report_transaction(host, &escrow);
+            
+            logger.log(&EscrowEvent::TransactionCompleted {
+                escrow_id,
+                seller_amount,
+                platform_fee: platform_fee_amount,
+            })?;
+            
+            Ok(())
+        }
+        
+        UpdateParams::ClaimRefund { escrow_id } => {
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            
+            // Only buyer can claim a refund
+            if ctx.sender() != escrow.buyer {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            // Refund is only possible while the seller has not confirmed delivery
+            if escrow.status != EscrowStatus::Created && escrow.status != EscrowStatus::Active {
+                return Err(EscrowError::InvalidStatus);
+            }
+            if escrow.milestones.iter().any(|m| {
+                m.status == MilestoneStatus::DeliveryConfirmed || m.status == MilestoneStatus::Disputed
+            }) {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            // Escrows without a refund deadline can only be cancelled by the admin
+            match escrow.refund_deadline {
+                Some(deadline) if ctx.metadata().slot_time() >= deadline => {}
+                _ => return Err(EscrowError::RefundDeadlineNotReached),
+            }
+            
+            // Refund to buyer (milestones already released stay with the seller)
+            let refund_amount = take_outstanding_amount(&mut escrow);
+            transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.buyer, refund_amount)?;
+            unlock_funds(host, &escrow, refund_amount);
+            
+            escrow.status = EscrowStatus::Refunded;
+            
+            logger.log(&EscrowEvent::EscrowRefunded {
+                escrow_id,
+                refund_amount,
+            })?;
             
+            Ok(())
+        }
+        
+        UpdateParams::ReleaseAfterTimeout { escrow_id } => {
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            
+            // A dispute moves the escrow out of DeliveryConfirmed and blocks the release
+            if escrow.status != EscrowStatus::DeliveryConfirmed {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            match escrow.release_deadline {
+                Some(deadline) if ctx.metadata().slot_time() >= deadline => {}
+                _ => return Err(EscrowError::ConfirmationWindowNotElapsed),
+            }
+            
+            // Calculate platform fee and seller amount
+            let platform_fee_amount = calculate_platform_fee(escrow.amount, state.platform_fee);
+            let seller_amount = escrow.amount - platform_fee_amount;
+            
+            // Transfer to seller
+            if seller_amount > Amount::zero() {
+                transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.seller, seller_amount)?;
+            }
+            
+            collect_platform_fee(host, ctx.self_address(), &escrow, platform_fee_amount)?;
+            unlock_funds(host, &escrow, escrow.amount);
             escrow.status = EscrowStatus::Completed;
             
+            report_transaction(host, &escrow);
+            
             logger.log(&EscrowEvent::TransactionCompleted {
                 escrow_id,
                 seller_amount,
@@ -399,78 +1055,151 @@ fn update(
             Ok(())
         }
         
-        UpdateParams::RaiseDispute { escrow_id, reason: _ } => {
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+        UpdateParams::RaiseDispute {
+            escrow_id,
+            milestone_index,
+            reason,
+            evidence,
+            seed_commit,
+        } => {
+            let escrow = state.escrows.get(&escrow_id).map(|escrow| escrow.clone()).ok_or(EscrowError::EscrowNotFound)?;
             
             // Only buyer or seller can raise dispute
             if ctx.sender() != escrow.buyer && ctx.sender() != escrow.seller {
                 return Err(EscrowError::Unauthorized);
             }
             
-            // Can only dispute active or delivery-confirmed escrows
+            // Can only dispute active or delivery-confirmed escrows, one milestone at a time
             if escrow.status != EscrowStatus::Active && escrow.status != EscrowStatus::DeliveryConfirmed {
                 return Err(EscrowError::InvalidStatus);
             }
+            if escrow.disputed_milestone.is_some() {
+                return Err(EscrowError::InvalidStatus);
+            }
+            
+            // Milestone escrows are disputed one open milestone at a time
+            let disputed_amount = match milestone_index {
+                None if escrow.milestones.is_empty() => escrow.amount,
+                Some(index) => {
+                    let milestone = escrow.milestones.get(index as usize).ok_or(EscrowError::InvalidMilestone)?;
+                    if milestone.status != MilestoneStatus::Pending
+                        && milestone.status != MilestoneStatus::DeliveryConfirmed
+                    {
+                        return Err(EscrowError::InvalidStatus);
+                    }
+                    milestone.amount
+                }
+                None => return Err(EscrowError::InvalidMilestone),
+            };
+            
+            let dispute_contract = state.dispute_contract.ok_or(EscrowError::DisputeContractNotSet)?;
             
-            escrow.status = EscrowStatus::Disputed;
+            // Create the dispute in the dispute resolution contract
+            let create_dispute = DisputeUpdateParams::CreateDispute {
+                escrow_id,
+                buyer: escrow.buyer,
+                seller: escrow.seller,
+                amount: disputed_amount,
+                reason,
+                evidence,
+                seed_commit,
+            };
+            let (_, return_value) = host
+                .invoke_contract(
+                    &dispute_contract,
+                    &create_dispute,
+                    EntrypointName::new_unchecked("update"),
+                    Amount::zero(),
+                )
+                .map_err(|_| EscrowError::InvokeContractError)?;
+            let dispute_id: Option<DisputeId> = return_value
+                .ok_or(EscrowError::InvokeContractError)?
+                .get()
+                .map_err(|_| EscrowError::InvokeContractError)?;
+            let dispute_id = dispute_id.ok_or(EscrowError::InvokeContractError)?;
             
-            // In a full implementation, this would create a dispute in the dispute resolution contract
-            let dispute_id = escrow_id; // Simplified for MVP
+            // A milestone dispute only freezes the disputed milestone, the escrow stays active
+            let mut escrow = host.state_mut().escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             escrow.dispute_id = Some(dispute_id);
+            match milestone_index {
+                Some(index) => {
+                    escrow.milestones[index as usize].status = MilestoneStatus::Disputed;
+                    escrow.disputed_milestone = Some(index);
+                }
+                None => escrow.status = EscrowStatus::Disputed,
+            }
             
-            logger.log(&EscrowEvent::DisputeRaised { escrow_id, dispute_id })?;
+            logger.log(&EscrowEvent::DisputeRaised {
+                escrow_id,
+                milestone_index,
+                dispute_id,
+            })?;
             
             Ok(())
         }
         
-        UpdateParams::ResolveDispute { escrow_id, resolution } => {
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+        UpdateParams::ResolveDispute {
+            escrow_id,
+            dispute_id,
+            resolution,
+        } => {
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             
             // Only dispute contract or admin can resolve disputes
             if let Some(dispute_contract) = state.dispute_contract {
-                if ctx.sender() != AccountAddress::Contract(dispute_contract) && ctx.sender() != state.admin {
+                if ctx.sender() != Address::Contract(dispute_contract) && ctx.sender() != state.admin {
                     return Err(EscrowError::Unauthorized);
                 }
             } else if ctx.sender() != state.admin {
                 return Err(EscrowError::Unauthorized);
             }
             
-            if escrow.status != EscrowStatus::Disputed {
+            if escrow.status != EscrowStatus::Disputed && escrow.disputed_milestone.is_none() {
                 return Err(EscrowError::InvalidStatus);
             }
             
+            // The resolution must come from the dispute raised for this escrow
+            if escrow.dispute_id != Some(dispute_id) {
+                return Err(EscrowError::InvalidDisputeResolution);
+            }
+            
+            // Milestone disputes only settle the disputed milestone
+            let disputed_amount = match escrow.disputed_milestone {
+                Some(index) => escrow.milestones[index as usize].amount,
+                None => escrow.amount,
+            };
+            
             // Execute resolution
-            match resolution {
+            let outcome = match resolution {
                 DisputeResolution::FavorBuyer => {
                     // Refund to buyer
-                    host.invoke_transfer(&escrow.buyer, escrow.amount)
-                        .map_err(|_| EscrowError::TransferError)?;
-                    escrow.status = EscrowStatus::Refunded;
+                    transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.buyer, disputed_amount)?;
                     
                     logger.log(&EscrowEvent::EscrowRefunded {
                         escrow_id,
-                        refund_amount: escrow.amount,
+                        refund_amount: disputed_amount,
                     })?;
+                    
+                    MilestoneStatus::Refunded
                 }
                 DisputeResolution::FavorSeller => {
                     // Calculate platform fee and pay seller
-                    let platform_fee_amount = Amount::from_micro_ccd(
-                        (escrow.amount.micro_ccd() * u64::from(state.platform_fee)) / 10000
-                    );
-                    let seller_amount = escrow.amount - platform_fee_amount;
+                    let platform_fee_amount = calculate_platform_fee(disputed_amount, state.platform_fee);
+                    let seller_amount = disputed_amount - platform_fee_amount;
                     
                     if seller_amount > Amount::zero() {
-                        host.invoke_transfer(&escrow.seller, seller_amount)
-                            .map_err(|_| EscrowError::TransferError)?;
+                        transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.seller, seller_amount)?;
                     }
                     
-                    escrow.status = EscrowStatus::Completed;
+                    collect_platform_fee(host, ctx.self_address(), &escrow, platform_fee_amount)?;
                     
                     logger.log(&EscrowEvent::TransactionCompleted {
                         escrow_id,
                         seller_amount,
                         platform_fee: platform_fee_amount,
                     })?;
+                    
+                    MilestoneStatus::Released
                 }
                 DisputeResolution::Split { seller_percentage } => {
                     if seller_percentage > 100 {
@@ -478,22 +1207,34 @@ fn update(
                     }
                     
                     let seller_amount = Amount::from_micro_ccd(
-                        (escrow.amount.micro_ccd() * u64::from(seller_percentage)) / 100
+                        (disputed_amount.micro_ccd() * u64::from(seller_percentage)) / 100
                     );
-                    let buyer_refund = escrow.amount - seller_amount;
+                    let buyer_refund = disputed_amount - seller_amount;
                     
                     if seller_amount > Amount::zero() {
-                        host.invoke_transfer(&escrow.seller, seller_amount)
-                            .map_err(|_| EscrowError::TransferError)?;
+                        transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.seller, seller_amount)?;
                     }
                     
                     if buyer_refund > Amount::zero() {
-                        host.invoke_transfer(&escrow.buyer, buyer_refund)
-                            .map_err(|_| EscrowError::TransferError)?;
+                        transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.buyer, buyer_refund)?;
                     }
                     
-                    escrow.status = EscrowStatus::Completed;
+                    MilestoneStatus::Released
                 }
+            };
+            unlock_funds(host, &escrow, disputed_amount);
+            
+            match escrow.disputed_milestone.take() {
+                // Remaining milestones continue after a milestone dispute
+                Some(index) => settle_milestone(&mut escrow, index, outcome),
+                None if outcome == MilestoneStatus::Refunded => escrow.status = EscrowStatus::Refunded,
+                None => escrow.status = EscrowStatus::Completed,
+            }
+            
+            // Report winner and loser to the reputation contract
+            report_dispute_outcome(host, &escrow, &resolution, disputed_amount);
+            if !escrow.milestones.is_empty() && escrow.status == EscrowStatus::Completed {
+                report_transaction(host, &escrow);
             }
             
             logger.log(&EscrowEvent::DisputeResolved { escrow_id, resolution })?;
@@ -507,16 +1248,20 @@ fn update(
                 return Err(EscrowError::Unauthorized);
             }
             
-            let escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
+            let mut escrow = state.escrows.get_mut(&escrow_id).ok_or(EscrowError::EscrowNotFound)?;
             
-            // Can only cancel created or active escrows
+            // Can only cancel created or active escrows without an open milestone dispute
             if escrow.status != EscrowStatus::Created && escrow.status != EscrowStatus::Active {
                 return Err(EscrowError::InvalidStatus);
             }
+            if escrow.disputed_milestone.is_some() {
+                return Err(EscrowError::InvalidStatus);
+            }
             
-            // Refund to buyer
-            host.invoke_transfer(&escrow.buyer, escrow.amount)
-                .map_err(|_| EscrowError::TransferError)?;
+            // Refund to buyer (milestones already released stay with the seller)
+            let refund_amount = take_outstanding_amount(&mut escrow);
+            transfer_funds(host, ctx.self_address(), &escrow.token, &escrow.buyer, refund_amount)?;
+            unlock_funds(host, &escrow, refund_amount);
             
             escrow.status = EscrowStatus::Cancelled;
             
@@ -545,9 +1290,157 @@ fn update(
             state.dispute_contract = Some(contract_address);
             Ok(())
         }
+        
+        UpdateParams::SetReputationContract { contract_address } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            state.reputation_contract = Some(contract_address);
+            Ok(())
+        }
+        
+        UpdateParams::UpdateConfirmationWindow { hours } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            state.confirmation_window_hours = hours;
+            Ok(())
+        }
+        
+        UpdateParams::WithdrawFees { to, amount } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            // Only fees that have not been withdrawn yet are available
+            let available_fees = state.accrued_fees - state.withdrawn_fees;
+            if amount > available_fees {
+                return Err(EscrowError::InsufficientFunds);
+            }
+            
+            // Never touch the balance backing open escrows
+            if host.self_balance() < state.locked_balance + amount {
+                return Err(EscrowError::InsufficientFunds);
+            }
+            
+            let recipient = to.unwrap_or(state.fee_recipient);
+            host.invoke_transfer(&recipient, amount)
+                .map_err(|_| EscrowError::TransferError)?;
+            
+            state.withdrawn_fees += amount;
+            
+            logger.log(&EscrowEvent::FeesWithdrawn {
+                to: recipient,
+                amount,
+            })?;
+            
+            Ok(())
+        }
+        
+        UpdateParams::SetFeeRecipient { recipient } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            state.fee_recipient = recipient;
+            Ok(())
+        }
+        
+        UpdateParams::SetAcceptedToken { contract, accepted } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            if accepted {
+                state.accepted_tokens.insert(contract);
+            } else {
+                state.accepted_tokens.remove(&contract);
+            }
+            Ok(())
+        }
+        
+        UpdateParams::SetVerifierRegistry { contract_address } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            state.verifier_registry = Some(contract_address);
+            Ok(())
+        }
+        
+        UpdateParams::SetIdentityRequirements { requirements } => {
+            if ctx.sender() != state.admin {
+                return Err(EscrowError::Unauthorized);
+            }
+            
+            state.identity_requirements = requirements;
+            Ok(())
+        }
     }
 }
 
+/// Create an escrow funded with CIS-2 tokens
+///
+/// Called by the token contract when a buyer transfers tokens to this contract,
+/// with the escrow terms serialized in the transfer's additional data.
+#[receive(
+    contract = "credify_escrow",
+    name = "onReceivingCIS2",
+    parameter = "OnReceivingCis2DataParams<ContractTokenId, ContractTokenAmount, EscrowTerms>",
+    error = "EscrowError",
+    enable_logger,
+    mutable,
+    crypto_primitives
+)]
+fn on_receiving_cis2(
+    ctx: &ReceiveContext,
+    host: &mut Host<EscrowState>,
+    logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<()> {
+    let params: OnReceivingCis2DataParams<ContractTokenId, ContractTokenAmount, EscrowTerms> =
+        ctx.parameter_cursor().get().map_err(|_| EscrowError::ParseError)?;
+    
+    // Only accepted token contracts can deposit tokens
+    let token_contract = match ctx.sender() {
+        Address::Contract(contract) => contract,
+        Address::Account(_) => return Err(EscrowError::Unauthorized),
+    };
+    if !host.state().accepted_tokens.contains(&token_contract) {
+        return Err(EscrowError::TokenNotAccepted);
+    }
+    
+    // Tokens must come from the buyer's account
+    let buyer = match params.from {
+        Address::Account(account) => account,
+        Address::Contract(_) => return Err(EscrowError::Unauthorized),
+    };
+    
+    let amount = Amount::from_micro_ccd(params.amount.0);
+    if amount == Amount::zero() {
+        return Err(EscrowError::InsufficientFunds);
+    }
+    
+    let token = EscrowToken {
+        contract: token_contract,
+        token_id: params.token_id,
+    };
+    create_escrow(
+        host.state_mut(),
+        logger,
+        crypto_primitives,
+        buyer,
+        amount,
+        Some(token),
+        params.data,
+        ctx.metadata().slot_time(),
+    )?;
+    
+    Ok(())
+}
+
 /// Get escrow details by ID
 #[receive(
     contract = "credify_escrow",
@@ -558,7 +1451,33 @@ fn update(
 )]
 fn get_escrow(ctx: &ReceiveContext, host: &Host<EscrowState>) -> ContractResult<Option<EscrowDetails>> {
     let escrow_id: EscrowId = ctx.parameter_cursor().get().map_err(|_| EscrowError::ParseError)?;
-    Ok(host.state().escrows.get(&escrow_id).cloned())
+    Ok(host.state().escrows.get(&escrow_id).map(|escrow| escrow.clone()))
+}
+
+/// Get escrow details by the buyer's order reference
+#[receive(
+    contract = "credify_escrow",
+    name = "get_escrow_by_ref",
+    parameter = "EscrowRefQuery",
+    return_value = "Option<EscrowDetails>",
+    error = "EscrowError",
+    crypto_primitives
+)]
+fn get_escrow_by_ref(
+    ctx: &ReceiveContext,
+    host: &Host<EscrowState>,
+    crypto_primitives: &CryptoPrimitives,
+) -> ContractResult<Option<EscrowDetails>> {
+    let query: EscrowRefQuery = ctx.parameter_cursor().get().map_err(|_| EscrowError::ParseError)?;
+    let escrow_id = compute_escrow_id(crypto_primitives, &query.buyer, &query.order_ref);
+    
+    // Only return the escrow if it was created for this buyer and order reference
+    Ok(host
+        .state()
+        .escrows
+        .get(&escrow_id)
+        .filter(|escrow| escrow.buyer == query.buyer && escrow.order_ref.as_ref() == Some(&query.order_ref))
+        .map(|escrow| escrow.clone()))
 }
 
 /// Get platform fee
@@ -569,4 +1488,420 @@ fn get_escrow(ctx: &ReceiveContext, host: &Host<EscrowState>) -> ContractResult<
 )]
 fn get_platform_fee(_ctx: &ReceiveContext, host: &Host<EscrowState>) -> ReceiveResult<u32> {
     Ok(host.state().platform_fee)
+}
+
+/// Get accrued and withdrawn platform fee totals
+///
+/// Covers CCD fees only. Fees on token escrows are paid to the fee recipient directly at release.
+#[receive(
+    contract = "credify_escrow",
+    name = "get_fee_info",
+    return_value = "FeeInfo"
+)]
+fn get_fee_info(_ctx: &ReceiveContext, host: &Host<EscrowState>) -> ReceiveResult<FeeInfo> {
+    let state = host.state();
+    Ok(FeeInfo {
+        fee_recipient: state.fee_recipient,
+        accrued_fees: state.accrued_fees,
+        withdrawn_fees: state.withdrawn_fees,
+    })
+}
+
+// Helper functions
+
+/// Store a new escrow and log its creation
+fn create_escrow(
+    state: &mut EscrowState,
+    logger: &mut Logger,
+    crypto_primitives: &CryptoPrimitives,
+    buyer: AccountAddress,
+    amount: Amount,
+    token: Option<EscrowToken>,
+    terms: EscrowTerms,
+    now: Timestamp,
+) -> ContractResult<EscrowId> {
+    if terms.category.as_ref().map_or(false, |category| category.len() > MAX_CATEGORY_LENGTH) {
+        return Err(EscrowError::InvalidCategory);
+    }
+    if terms.order_ref.as_ref().map_or(false, |order_ref| order_ref.len() > MAX_ORDER_REF_LENGTH) {
+        return Err(EscrowError::InvalidOrderRef);
+    }
+    
+    // Milestone amounts must cover exactly the funded amount
+    if !terms.milestones.is_empty() {
+        let milestone_total = terms
+            .milestones
+            .iter()
+            .fold(Amount::zero(), |total, milestone| total + milestone.amount);
+        if milestone_total != amount || terms.milestones.iter().any(|m| m.amount == Amount::zero()) {
+            return Err(EscrowError::MilestoneAmountMismatch);
+        }
+    }
+    let milestones = terms
+        .milestones
+        .into_iter()
+        .map(|milestone| Milestone {
+            description: milestone.description,
+            amount: milestone.amount,
+            status: MilestoneStatus::Pending,
+            release_deadline: None,
+        })
+        .collect();
+    
+    // Calculate refund deadline if specified
+    let refund_deadline = terms.refund_deadline_hours.map(|hours| {
+        now.add_duration(Duration::from_hours(hours))
+    });
+    
+    // Escrows with an order reference get a stable id the backend can compute
+    let escrow_id = match &terms.order_ref {
+        Some(order_ref) => {
+            let escrow_id = compute_escrow_id(crypto_primitives, &buyer, order_ref);
+            if state.escrows.get(&escrow_id).is_some() {
+                return Err(EscrowError::DuplicateOrderRef);
+            }
+            escrow_id
+        }
+        None => {
+            let escrow_id = state.next_escrow_id;
+            state.next_escrow_id += 1;
+            escrow_id
+        }
+    };
+    
+    // Create new escrow
+    let escrow = EscrowDetails {
+        buyer,
+        seller: terms.seller,
+        amount,
+        token: token.clone(),
+        description: terms.description,
+        status: EscrowStatus::Created,
+        created_at: now,
+        refund_deadline,
+        requires_identity_verification: terms.requires_identity_verification,
+        buyer_verified: !terms.requires_identity_verification, // If no verification required, mark as verified
+        seller_verified: !terms.requires_identity_verification,
+        dispute_id: None,
+        confirmation_window_hours: terms.confirmation_window_hours,
+        release_deadline: None,
+        milestones,
+        disputed_milestone: None,
+        order_ref: terms.order_ref.clone(),
+        category: terms.category,
+        buyer_identity: None,
+        seller_identity: None,
+    };
+    
+    state.escrows.insert(escrow_id, escrow);
+    if token.is_none() {
+        state.locked_balance += amount;
+    }
+    
+    // Log event
+    logger.log(&EscrowEvent::EscrowCreated {
+        escrow_id,
+        buyer,
+        seller: terms.seller,
+        amount,
+        token,
+        order_ref: terms.order_ref,
+    })?;
+    
+    Ok(escrow_id)
+}
+
+/// Derive the escrow id for an order reference from keccak256(buyer || order_ref), like `computeId` in EscrowUSDC.sol
+fn compute_escrow_id(crypto_primitives: &CryptoPrimitives, buyer: &AccountAddress, order_ref: &str) -> EscrowId {
+    let mut data = buyer.0.to_vec();
+    data.extend_from_slice(order_ref.as_bytes());
+    let hash = crypto_primitives.hash_keccak_256(&data);
+    
+    let mut id_bytes = [0u8; 8];
+    id_bytes.copy_from_slice(&hash.0[..8]);
+    u64::from_be_bytes(id_bytes) | ORDER_REF_ID_FLAG
+}
+
+/// Get an account's effective attestation from the verifier registry
+fn registry_attestation(host: &Host<EscrowState>, account: AccountAddress) -> ContractResult<Option<Attestation>> {
+    let verifier_registry = host.state().verifier_registry.ok_or(EscrowError::VerifierRegistryNotSet)?;
+    
+    host.invoke_contract_read_only(
+        &verifier_registry,
+        &account,
+        EntrypointName::new_unchecked("get_effective_attestation"),
+        Amount::zero(),
+    )
+    .map_err(|_| EscrowError::InvokeContractError)?
+    .ok_or(EscrowError::InvokeContractError)?
+    .get()
+    .map_err(|_| EscrowError::InvokeContractError)
+}
+
+/// Check a verifier's identity attestation for an account against the verifier registry and requirements
+fn verify_identity_attestation(
+    host: &Host<EscrowState>,
+    self_address: ContractAddress,
+    account: AccountAddress,
+    attestation: &IdentityAttestation,
+    now: Timestamp,
+) -> ContractResult<()> {
+    let state = host.state();
+    
+    // The signer must be the verifier behind the account's effective attestation in the registry,
+    // so revoked attestations and removed verifiers are no longer accepted
+    let registry_attestation =
+        registry_attestation(host, account)?.ok_or(EscrowError::IdentityVerificationRequired)?;
+    if registry_attestation.verifier != attestation.verifier {
+        return Err(EscrowError::UntrustedVerifier);
+    }
+    if now > attestation.expires_at {
+        return Err(EscrowError::AttestationExpired);
+    }
+    
+    let message = IdentityAttestationMessage {
+        contract: self_address,
+        subject: account,
+        attributes: attestation.attributes.clone(),
+        expires_at: attestation.expires_at,
+    };
+    let valid = host
+        .check_account_signature(attestation.verifier, &attestation.signature, &to_bytes(&message))
+        .map_err(|_| EscrowError::InvalidAttestation)?;
+    if !valid {
+        return Err(EscrowError::InvalidAttestation);
+    }
+    
+    let requirements = &state.identity_requirements;
+    let country_allowed = requirements.allowed_countries.is_empty()
+        || attestation
+            .attributes
+            .country
+            .as_ref()
+            .map_or(false, |country| requirements.allowed_countries.contains(country));
+    if attestation.attributes.age_over < requirements.min_age || !country_allowed {
+        return Err(EscrowError::IdentityRequirementsNotMet);
+    }
+    
+    Ok(())
+}
+
+/// Transfer escrowed funds in CCD, or through the token contract for CIS-2 escrows
+fn transfer_funds(
+    host: &mut Host<EscrowState>,
+    self_address: ContractAddress,
+    token: &Option<EscrowToken>,
+    to: &AccountAddress,
+    amount: Amount,
+) -> ContractResult<()> {
+    match token {
+        None => host.invoke_transfer(to, amount).map_err(|_| EscrowError::TransferError),
+        Some(token) => {
+            let transfer = Transfer {
+                token_id: token.token_id.clone(),
+                amount: TokenAmountU64(amount.micro_ccd()),
+                from: Address::Contract(self_address),
+                to: Receiver::from_account(*to),
+                data: AdditionalData::empty(),
+            };
+            Cis2Client::new(token.contract)
+                .transfer::<_, _, _, ()>(host, transfer)
+                .map_err(|_| EscrowError::TransferError)?;
+            Ok(())
+        }
+    }
+}
+
+/// Keep the platform fee of a settled escrow
+///
+/// CCD fees stay in the contract until withdrawn, while CIS-2 fees are
+/// forwarded to the fee recipient right away (as in `EscrowUSDC.sol`).
+fn collect_platform_fee(
+    host: &mut Host<EscrowState>,
+    self_address: ContractAddress,
+    escrow: &EscrowDetails,
+    fee: Amount,
+) -> ContractResult<()> {
+    if escrow.token.is_none() {
+        host.state_mut().accrued_fees += fee;
+    } else if fee > Amount::zero() {
+        let fee_recipient = host.state().fee_recipient;
+        transfer_funds(host, self_address, &escrow.token, &fee_recipient, fee)?;
+    }
+    Ok(())
+}
+
+/// Report a completed transaction for buyer and seller to the reputation contract
+///
+/// Token escrows are not reported, as their amounts are not denominated in CCD.
+fn report_transaction(host: &mut Host<EscrowState>, escrow: &EscrowDetails) {
+    let reputation_contract = match host.state().reputation_contract {
+        Some(contract) if escrow.token.is_none() => contract,
+        _ => return,
+    };
+    
+    for (account, is_buyer) in [(escrow.buyer, true), (escrow.seller, false)] {
+        let update = ReputationUpdateParams::UpdateFromTransaction {
+            account,
+            transaction_value: released_amount(escrow),
+            is_buyer,
+            category: escrow.category.clone(),
+        };
+        update_reputation(host, &reputation_contract, &update);
+    }
+}
+
+/// Report the winner and loser of a resolved dispute to the reputation contract
+///
+/// Token escrows are not reported, as their amounts are not denominated in CCD.
+fn report_dispute_outcome(
+    host: &mut Host<EscrowState>,
+    escrow: &EscrowDetails,
+    resolution: &DisputeResolution,
+    disputed_amount: Amount,
+) {
+    let reputation_contract = match host.state().reputation_contract {
+        Some(contract) if escrow.token.is_none() => contract,
+        _ => return,
+    };
+    
+    let (winner_is_buyer, dispute_value) = match resolution {
+        DisputeResolution::FavorBuyer => (true, disputed_amount),
+        DisputeResolution::FavorSeller => (false, disputed_amount),
+        DisputeResolution::Split { seller_percentage } => {
+            // Partial outcome: the party awarded the larger share wins, weighted by the margin
+            let seller_share = u64::from(*seller_percentage);
+            let buyer_share = 100 - seller_share;
+            if seller_share == buyer_share {
+                return;
+            }
+            
+            let margin = seller_share.abs_diff(buyer_share);
+            let dispute_value = Amount::from_micro_ccd((disputed_amount.micro_ccd() * margin) / 100);
+            (buyer_share > seller_share, dispute_value)
+        }
+    };
+    let (winner, loser) = if winner_is_buyer {
+        (escrow.buyer, escrow.seller)
+    } else {
+        (escrow.seller, escrow.buyer)
+    };
+    
+    let update = ReputationUpdateParams::UpdateFromDispute {
+        winner,
+        loser,
+        dispute_value,
+        winner_is_buyer,
+        category: escrow.category.clone(),
+    };
+    update_reputation(host, &reputation_contract, &update);
+}
+
+/// Invoke the reputation contract's update entrypoint
+///
+/// Best effort: a failing reputation contract must not block payouts and refunds.
+fn update_reputation(
+    host: &mut Host<EscrowState>,
+    reputation_contract: &ContractAddress,
+    update: &ReputationUpdateParams,
+) {
+    let _ = host.invoke_contract(
+        reputation_contract,
+        update,
+        EntrypointName::new_unchecked("update"),
+        Amount::zero(),
+    );
+}
+
+/// Calculate the platform fee for an amount (fee in basis points)
+fn calculate_platform_fee(amount: Amount, fee_basis_points: u32) -> Amount {
+    Amount::from_micro_ccd((amount.micro_ccd() * u64::from(fee_basis_points)) / 10000)
+}
+
+/// Release CCD of a settled escrow (or milestone) from the locked balance
+fn unlock_funds(host: &mut Host<EscrowState>, escrow: &EscrowDetails, amount: Amount) {
+    if escrow.token.is_none() {
+        host.state_mut().locked_balance -= amount;
+    }
+}
+
+/// Pay out a delivered milestone to the seller and settle it
+///
+/// Returns the seller amount and the platform fee.
+fn release_milestone(
+    host: &mut Host<EscrowState>,
+    self_address: ContractAddress,
+    escrow: &mut EscrowDetails,
+    index: MilestoneIndex,
+) -> ContractResult<(Amount, Amount)> {
+    let amount = escrow.milestones[index as usize].amount;
+    let platform_fee_amount = calculate_platform_fee(amount, host.state().platform_fee);
+    let seller_amount = amount - platform_fee_amount;
+    
+    if seller_amount > Amount::zero() {
+        transfer_funds(host, self_address, &escrow.token, &escrow.seller, seller_amount)?;
+    }
+    
+    collect_platform_fee(host, self_address, escrow, platform_fee_amount)?;
+    unlock_funds(host, escrow, amount);
+    settle_milestone(escrow, index, MilestoneStatus::Released);
+    
+    Ok((seller_amount, platform_fee_amount))
+}
+
+/// Amount of a completed escrow that went to the seller's side (released milestones only)
+fn released_amount(escrow: &EscrowDetails) -> Amount {
+    if escrow.milestones.is_empty() {
+        return escrow.amount;
+    }
+    escrow
+        .milestones
+        .iter()
+        .filter(|m| m.status == MilestoneStatus::Released)
+        .fold(Amount::zero(), |total, m| total + m.amount)
+}
+
+/// Index of the first milestone that has not been settled yet
+fn next_milestone(escrow: &EscrowDetails) -> Option<MilestoneIndex> {
+    escrow
+        .milestones
+        .iter()
+        .position(|m| m.status != MilestoneStatus::Released && m.status != MilestoneStatus::Refunded)
+        .map(|index| index as MilestoneIndex)
+}
+
+/// Settle a milestone and update the escrow status once all milestones are settled
+fn settle_milestone(escrow: &mut EscrowDetails, index: MilestoneIndex, status: MilestoneStatus) {
+    escrow.milestones[index as usize].status = status;
+    
+    escrow.status = if next_milestone(escrow).is_some() {
+        EscrowStatus::Active
+    } else if escrow.milestones.iter().any(|m| m.status == MilestoneStatus::Released) {
+        EscrowStatus::Completed
+    } else {
+        EscrowStatus::Refunded
+    };
+}
+
+/// Amount of an escrow that has not been released or refunded yet
+fn outstanding_amount(escrow: &EscrowDetails) -> Amount {
+    if escrow.milestones.is_empty() {
+        return escrow.amount;
+    }
+    escrow
+        .milestones
+        .iter()
+        .filter(|m| m.status != MilestoneStatus::Released && m.status != MilestoneStatus::Refunded)
+        .fold(Amount::zero(), |total, m| total + m.amount)
+}
+
+/// Mark all outstanding milestones as refunded and return the amount to refund
+fn take_outstanding_amount(escrow: &mut EscrowDetails) -> Amount {
+    let amount = outstanding_amount(escrow);
+    for milestone in escrow.milestones.iter_mut() {
+        if milestone.status != MilestoneStatus::Released {
+            milestone.status = MilestoneStatus::Refunded;
+        }
+    }
+    amount
 }
\ No newline at end of file
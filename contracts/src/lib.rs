@@ -4,8 +4,10 @@
 pub mod escrow;
 pub mod reputation;
 pub mod dispute_resolution;
+pub mod verifier_registry;
 
 // Re-export main contracts
 pub use escrow::*;
 pub use reputation::*;
-pub use dispute_resolution::*;
\ No newline at end of file
+pub use dispute_resolution::*;
+pub use verifier_registry::*;
\ No newline at end of file
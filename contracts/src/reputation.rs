@@ -10,6 +10,9 @@ Features:
 - Automated reputation updates
 - Integration with escrow and dispute systems
 - Reputation decay over time for inactive accounts
+- Verification levels that expire after 12 months
+- Score history checkpoints for point-in-time lookups
+- Sub-scores per role (buyer, seller, juror) and admin-registered product category
 */
 
 use concordium_cis2::*;
@@ -25,14 +28,30 @@ pub struct ReputationState<S: HasStateApi> {
     pub reputation_scores: StateMap<AccountAddress, u64, S>,
     /// Map from account to their reputation token ID
     pub account_tokens: StateMap<AccountAddress, TokenIdU32, S>,
+    /// Map from account to their recent score checkpoints
+    pub score_history: StateMap<AccountAddress, ScoreHistory, S>,
+    /// Map from account to their sub-scores
+    pub breakdowns: StateMap<AccountAddress, ReputationBreakdown, S>,
     /// Next token ID to assign
     pub next_token_id: TokenIdU32,
+    /// Token ID where the next reputation decay batch starts
+    pub decay_cursor: TokenIdU32,
+    /// Token ID where the next verification expiry batch starts
+    pub expiry_cursor: TokenIdU32,
     /// Admin account
     pub admin: AccountAddress,
     /// Escrow contract address (authorized to update reputation)
     pub escrow_contract: Option<ContractAddress>,
+    /// Dispute resolution contract address (authorized to update juror sub-scores)
+    pub dispute_contract: Option<ContractAddress>,
+    /// Verifier registry contract (authorized to set verification levels)
+    pub verifier_registry: Option<ContractAddress>,
     /// Base reputation score for new accounts
     pub base_reputation: u64,
+    /// Base URL for token metadata (tier and token ID are appended)
+    pub metadata_base_url: String,
+    /// Product categories scored in sub-scores (admin-managed, at most MAX_CATEGORIES)
+    pub categories: Vec<String>,
 }
 
 // Reputation data for each token
@@ -52,10 +71,157 @@ pub struct ReputationData {
     pub disputes_won: u64,
     /// Timestamp of last activity
     pub last_activity: Timestamp,
+    /// Number of inactivity periods already decayed since last activity
+    pub decay_periods_applied: u64,
     /// Total value of transactions (in microCCD)
     pub total_transaction_value: u64,
     /// Account verification level
     pub verification_level: VerificationLevel,
+    /// Timestamp the current verification level was granted or renewed
+    pub verified_at: Option<Timestamp>,
+    /// Timestamp after which the current verification level expires
+    pub expires_at: Option<Timestamp>,
+}
+
+// Score at a point in time
+#[derive(Serialize, SchemaType, Clone)]
+pub struct ScoreCheckpoint {
+    /// Timestamp of the score change
+    pub timestamp: Timestamp,
+    /// Score after the change
+    pub score: u64,
+    /// Reason for the change
+    pub reason: UpdateReason,
+}
+
+// Recent score checkpoints kept as a ring buffer
+#[derive(Serialize, SchemaType, Clone, Default)]
+pub struct ScoreHistory {
+    /// Checkpoints, overwritten oldest first once MAX_SCORE_CHECKPOINTS is reached
+    pub checkpoints: Vec<ScoreCheckpoint>,
+    /// Index of the oldest checkpoint once the buffer is full, and the next one to overwrite
+    pub next_index: u32,
+    /// Lowest score among overwritten checkpoints
+    pub evicted_floor: Option<u64>,
+    /// Timestamp the account's reputation token was created
+    pub created_at: Option<Timestamp>,
+}
+
+impl ScoreHistory {
+    /// Append a checkpoint, overwriting the oldest once the buffer is full
+    fn push(&mut self, checkpoint: ScoreCheckpoint) {
+        if matches!(checkpoint.reason, UpdateReason::Created) {
+            self.created_at = Some(checkpoint.timestamp);
+        }
+        if self.checkpoints.len() < MAX_SCORE_CHECKPOINTS {
+            self.checkpoints.push(checkpoint);
+        } else {
+            // Overwritten scores are kept as a floor, so flooding the buffer cannot inflate older lookups
+            let evicted = core::mem::replace(&mut self.checkpoints[self.next_index as usize], checkpoint);
+            self.evicted_floor = Some(self.evicted_floor.map_or(evicted.score, |floor| floor.min(evicted.score)));
+        }
+        self.next_index = (self.next_index + 1) % MAX_SCORE_CHECKPOINTS as u32;
+    }
+    
+    /// Checkpoints from oldest to newest
+    fn iter(&self) -> impl Iterator<Item = &ScoreCheckpoint> {
+        // Until the buffer is full next_index equals its length, so this is oldest-first either way
+        let (newer, older) = self.checkpoints.split_at(self.next_index as usize);
+        older.iter().chain(newer.iter())
+    }
+    
+    /// Score of the latest checkpoint at or before the timestamp
+    ///
+    /// Before the account was created this is 0. If the retained history does not reach back far
+    /// enough, the lowest overwritten score is used instead (or the lowest retained score if
+    /// nothing was overwritten yet).
+    fn score_at(&self, timestamp: Timestamp) -> u64 {
+        if self.created_at.map_or(false, |created_at| timestamp < created_at) {
+            return 0;
+        }
+        if let Some(checkpoint) = self.iter().take_while(|checkpoint| checkpoint.timestamp <= timestamp).last() {
+            return checkpoint.score;
+        }
+        self.evicted_floor
+            .unwrap_or_else(|| self.iter().map(|checkpoint| checkpoint.score).min().unwrap_or(0))
+    }
+}
+
+// Sub-scores per role and product category
+#[derive(Serialize, SchemaType, Clone, Default)]
+pub struct ReputationBreakdown {
+    /// Score earned as a buyer
+    pub as_buyer: u64,
+    /// Score earned as a seller
+    pub as_seller: u64,
+    /// Score earned as a juror
+    pub as_juror: u64,
+    /// Scores per registered product category (at most MAX_CATEGORIES)
+    pub categories: Vec<CategoryScore>,
+}
+
+// Sub-score for one product category
+#[derive(Serialize, SchemaType, Clone)]
+pub struct CategoryScore {
+    pub category: String,
+    pub score: u64,
+}
+
+impl ReputationBreakdown {
+    /// Add points to a role and, if given, a product category
+    fn credit(&mut self, is_buyer: bool, category: Option<&str>, points: u64) {
+        *self.role_mut(is_buyer) += points;
+        if let Some(score) = category.and_then(|c| self.category_mut(c)) {
+            *score += points;
+        }
+    }
+    
+    /// Remove points from a role and, if given, a product category
+    fn debit(&mut self, is_buyer: bool, category: Option<&str>, points: u64) {
+        let role = self.role_mut(is_buyer);
+        *role = role.saturating_sub(points);
+        if let Some(score) = category.and_then(|c| self.category_mut(c)) {
+            *score = score.saturating_sub(points);
+        }
+    }
+    
+    fn role_mut(&mut self, is_buyer: bool) -> &mut u64 {
+        if is_buyer {
+            &mut self.as_buyer
+        } else {
+            &mut self.as_seller
+        }
+    }
+    
+    /// Category sub-score, added while fewer than MAX_CATEGORIES are tracked
+    fn category_mut(&mut self, category: &str) -> Option<&mut u64> {
+        match self.categories.iter().position(|c| c.category == category) {
+            Some(index) => Some(&mut self.categories[index].score),
+            None if self.categories.len() < MAX_CATEGORIES => {
+                self.categories.push(CategoryScore {
+                    category: category.into(),
+                    score: 0,
+                });
+                self.categories.last_mut().map(|c| &mut c.score)
+            }
+            None => None,
+        }
+    }
+}
+
+// How a juror voted relative to a dispute's resolution
+#[derive(Serialize, SchemaType, Clone)]
+pub struct JurorOutcome {
+    pub juror: AccountAddress,
+    /// Whether the juror revealed a vote agreeing with the resolution
+    pub voted_with_resolution: bool,
+}
+
+// Query for an account's score at a point in time
+#[derive(Serialize, SchemaType)]
+pub struct ScoreAtQuery {
+    pub account: AccountAddress,
+    pub timestamp: Timestamp,
 }
 
 // Account verification levels
@@ -71,11 +237,47 @@ pub enum VerificationLevel {
     Professional,
 }
 
+// Reputation tiers used for token metadata
+#[derive(Serialize, SchemaType, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReputationTier {
+    /// Score below 250
+    Bronze,
+    /// Score from 250 to 499
+    Silver,
+    /// Score from 500 to 999
+    Gold,
+    /// Score of 1000 and above
+    Platinum,
+}
+
+impl ReputationTier {
+    /// Tier for a reputation score
+    pub fn from_score(score: u64) -> Self {
+        match score {
+            0..=249 => ReputationTier::Bronze,
+            250..=499 => ReputationTier::Silver,
+            500..=999 => ReputationTier::Gold,
+            _ => ReputationTier::Platinum,
+        }
+    }
+    
+    /// Path segment used in metadata URLs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReputationTier::Bronze => "bronze",
+            ReputationTier::Silver => "silver",
+            ReputationTier::Gold => "gold",
+            ReputationTier::Platinum => "platinum",
+        }
+    }
+}
+
 // Contract initialization parameters
 #[derive(Serialize, SchemaType)]
 pub struct InitParams {
     pub admin: AccountAddress,
     pub base_reputation: u64,
+    pub metadata_base_url: String,
 }
 
 // Custom update parameters (beyond CIS-2)
@@ -86,24 +288,65 @@ pub enum ReputationUpdateParams {
         account: AccountAddress,
         transaction_value: Amount,
         is_buyer: bool,
+        /// Product category of the transaction (only registered categories are scored)
+        category: Option<String>,
     },
     /// Update reputation after dispute resolution
     UpdateFromDispute {
         winner: AccountAddress,
         loser: AccountAddress,
         dispute_value: Amount,
+        /// Whether the winner was the buyer (the loser had the other role)
+        winner_is_buyer: bool,
+        /// Product category of the disputed transaction (only registered categories are scored)
+        category: Option<String>,
+    },
+    /// Update juror sub-scores after a dispute round is resolved
+    UpdateFromJurors {
+        outcomes: Vec<JurorOutcome>,
     },
     /// Set account verification level
     SetVerificationLevel {
         account: AccountAddress,
         level: VerificationLevel,
+        /// Timestamp the level was verified (it expires 12 months later)
+        verified_at: Timestamp,
     },
     /// Set escrow contract address (admin only)
     SetEscrowContract {
         contract_address: ContractAddress,
     },
-    /// Decay reputation for inactive accounts
-    DecayInactiveReputation,
+    /// Set dispute resolution contract address (admin only)
+    SetDisputeContract {
+        contract_address: ContractAddress,
+    },
+    /// Set verifier registry contract address (admin only)
+    SetVerifierRegistry {
+        contract_address: ContractAddress,
+    },
+    /// Set base URL for token metadata (admin only)
+    SetMetadataBaseUrl {
+        url: String,
+    },
+    /// Register or remove a product category scored in sub-scores (admin only)
+    SetCategory {
+        category: String,
+        enabled: bool,
+    },
+    /// Decay reputation for a bounded batch of inactive accounts
+    DecayBatch {
+        /// First token to process (continues from the stored cursor if None)
+        start_token: Option<TokenIdU32>,
+        /// Maximum number of tokens to process
+        max_items: u32,
+    },
+    /// Reset expired verification levels for a bounded batch of accounts
+    ExpireVerifications {
+        /// First token to process (continues from the stored cursor if None)
+        start_token: Option<TokenIdU32>,
+        /// Maximum number of tokens to process
+        max_items: u32,
+    },
 }
 
 // Contract errors
@@ -119,10 +362,14 @@ pub enum ReputationError {
     AccountNotFound,
     /// Reputation tokens cannot be transferred
     TransferProhibited,
+    /// Reputation tokens cannot be managed by operators
+    OperatorsProhibited,
     /// Parse error
     ParseError,
     /// Invalid verification level
     InvalidVerificationLevel,
+    /// Category name too long or category limit reached
+    InvalidCategory,
 }
 
 impl From<Cis2Error<TokenIdU32>> for ReputationError {
@@ -153,15 +400,30 @@ pub enum ReputationEvent {
         old_level: VerificationLevel,
         new_level: VerificationLevel,
     },
+    /// Reputation decay applied to a batch of tokens
+    DecayBatchProcessed {
+        start_token: TokenIdU32,
+        end_token: TokenIdU32,
+        accounts_decayed: u32,
+    },
+    /// Verification expiry applied to a batch of tokens
+    ExpiryBatchProcessed {
+        start_token: TokenIdU32,
+        end_token: TokenIdU32,
+        accounts_expired: u32,
+    },
 }
 
-#[derive(Debug, Serialize, SchemaType)]
+#[derive(Debug, Serialize, SchemaType, Clone)]
 pub enum UpdateReason {
+    Created,
     Transaction,
     DisputeWon,
     DisputeLost,
     Verification,
+    VerificationExpired,
     Decay,
+    Snapshot,
 }
 
 /// Contract token ID type
@@ -187,10 +449,18 @@ fn init<S: HasStateApi>(
         tokens: state_builder.new_map(),
         reputation_scores: state_builder.new_map(),
         account_tokens: state_builder.new_map(),
+        score_history: state_builder.new_map(),
+        breakdowns: state_builder.new_map(),
         next_token_id: 0,
+        decay_cursor: 0,
+        expiry_cursor: 0,
         admin: params.admin,
         escrow_contract: None,
+        dispute_contract: None,
+        verifier_registry: None,
         base_reputation: params.base_reputation,
+        metadata_base_url: params.metadata_base_url,
+        categories: Vec::new(),
     };
     
     Ok(state)
@@ -243,9 +513,11 @@ fn supports<S: HasStateApi>(
     let mut response = Vec::with_capacity(params.queries.len());
     
     for query in params.queries {
-        let support = match query {
-            StandardIdentifierOwned::new_unchecked("CIS-2".to_string()) => SupportResult::Support,
-            _ => SupportResult::NoSupport,
+        let identifier = query.as_standard_identifier();
+        let support = if identifier == CIS0_STANDARD_IDENTIFIER || identifier == CIS2_STANDARD_IDENTIFIER {
+            SupportResult::Support
+        } else {
+            SupportResult::NoSupport
         };
         response.push(support);
     }
@@ -269,6 +541,66 @@ fn transfer<S: HasStateApi>(
     Err(ReputationError::TransferProhibited)
 }
 
+/// Operators are not supported for reputation tokens
+#[receive(
+    contract = "credify_reputation",
+    name = "updateOperator",
+    parameter = "UpdateOperatorParams",
+    error = "ReputationError",
+    mutable
+)]
+fn update_operator<S: HasStateApi>(
+    _ctx: &ReceiveContext,
+    _host: &mut Host<ReputationState<S>>,
+) -> ContractResult<()> {
+    // Nobody can act on behalf of a reputation token owner
+    Err(ReputationError::OperatorsProhibited)
+}
+
+/// Implement CIS-2 operator_of - no address is ever an operator
+#[receive(
+    contract = "credify_reputation",
+    name = "operatorOf",
+    parameter = "OperatorOfQueryParams",
+    return_value = "OperatorOfQueryResponse",
+    error = "ReputationError"
+)]
+fn operator_of<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    _host: &Host<ReputationState<S>>,
+) -> ContractResult<OperatorOfQueryResponse> {
+    let params: OperatorOfQueryParams = ctx.parameter_cursor().get().map_err(|_| ReputationError::ParseError)?;
+    Ok(OperatorOfQueryResponse::from(vec![false; params.queries.len()]))
+}
+
+/// Implement CIS-2 token_metadata - the URL reflects the current score tier
+#[receive(
+    contract = "credify_reputation",
+    name = "tokenMetadata",
+    parameter = "TokenMetadataQueryParams<ContractTokenId>",
+    return_value = "TokenMetadataQueryResponse",
+    error = "ReputationError"
+)]
+fn token_metadata<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    host: &Host<ReputationState<S>>,
+) -> ContractResult<TokenMetadataQueryResponse> {
+    let params: TokenMetadataQueryParams<ContractTokenId> = ctx.parameter_cursor().get().map_err(|_| ReputationError::ParseError)?;
+    let state = host.state();
+    
+    let mut response = Vec::with_capacity(params.queries.len());
+    for token_id in params.queries {
+        let token_data = state.tokens.get(&token_id).ok_or(Cis2Error::InvalidTokenId)?;
+        let tier = ReputationTier::from_score(token_data.score);
+        response.push(MetadataUrl {
+            url: format!("{}/{}/{}", state.metadata_base_url, tier.as_str(), token_id),
+            hash: None,
+        });
+    }
+    
+    Ok(TokenMetadataQueryResponse::from(response))
+}
+
 /// Handle reputation-specific updates
 #[receive(
     contract = "credify_reputation",
@@ -291,17 +623,21 @@ fn update<S: HasStateApi>(
             account,
             transaction_value,
             is_buyer,
+            category,
         } => {
             // Only escrow contract or admin can update reputation
             if let Some(escrow_contract) = state.escrow_contract {
-                if ctx.sender() != AccountAddress::Contract(escrow_contract) && ctx.sender() != state.admin {
+                if ctx.sender() != Address::Contract(escrow_contract) && ctx.sender() != state.admin {
                     return Err(ReputationError::Unauthorized);
                 }
             } else if ctx.sender() != state.admin {
                 return Err(ReputationError::Unauthorized);
             }
             
-            let token_id = get_or_create_reputation_token(account, state, logger)?;
+            // Unregistered categories only count towards the role sub-score
+            let category = category.filter(|c| state.categories.contains(c));
+            
+            let token_id = get_or_create_reputation_token(account, ctx.metadata().slot_time(), state, logger)?;
             let mut token_data = state.tokens.get(&token_id).ok_or(ReputationError::TokenNotFound)?.clone();
             
             let old_score = token_data.score;
@@ -316,11 +652,18 @@ fn update<S: HasStateApi>(
             // Add transaction value
             token_data.total_transaction_value += transaction_value.micro_ccd();
             token_data.last_activity = ctx.metadata().slot_time();
+            token_data.decay_periods_applied = 0;
             
             // Calculate reputation increase
             let base_increase = if is_buyer { 10 } else { 15 }; // Sellers get slightly more reputation
             let value_bonus = (transaction_value.micro_ccd() / 1_000_000).min(50); // Max 50 bonus points
-            let verification_multiplier = match token_data.verification_level {
+            // An expired level no longer multiplies, even before the expiry batch resets it
+            let effective_level = if verification_expired(&token_data, ctx.metadata().slot_time()) {
+                VerificationLevel::None
+            } else {
+                token_data.verification_level.clone()
+            };
+            let verification_multiplier = match effective_level {
                 VerificationLevel::None => 1,
                 VerificationLevel::Basic => 2,
                 VerificationLevel::Full => 3,
@@ -329,10 +672,20 @@ fn update<S: HasStateApi>(
             
             let reputation_increase = (base_increase + value_bonus) * verification_multiplier;
             token_data.score += reputation_increase;
+            state.breakdowns.entry(account).or_insert(ReputationBreakdown::default()).modify(|breakdown| {
+                breakdown.credit(is_buyer, category.as_deref(), reputation_increase)
+            });
             
             // Update state
             state.tokens.insert(token_id, token_data);
-            state.reputation_scores.insert(account, token_data.score);
+            record_score(
+                &mut state.reputation_scores,
+                &mut state.score_history,
+                account,
+                ctx.metadata().slot_time(),
+                token_data.score,
+                UpdateReason::Transaction,
+            );
             
             logger.log(&ReputationEvent::ReputationUpdated {
                 account,
@@ -348,29 +701,45 @@ fn update<S: HasStateApi>(
             winner,
             loser,
             dispute_value,
+            winner_is_buyer,
+            category,
         } => {
             // Only escrow contract or admin can update reputation
             if let Some(escrow_contract) = state.escrow_contract {
-                if ctx.sender() != AccountAddress::Contract(escrow_contract) && ctx.sender() != state.admin {
+                if ctx.sender() != Address::Contract(escrow_contract) && ctx.sender() != state.admin {
                     return Err(ReputationError::Unauthorized);
                 }
             } else if ctx.sender() != state.admin {
                 return Err(ReputationError::Unauthorized);
             }
             
+            // Unregistered categories only count towards the role sub-scores
+            let category = category.filter(|c| state.categories.contains(c));
+            
             // Update winner's reputation
-            let winner_token_id = get_or_create_reputation_token(winner, state, logger)?;
+            let winner_token_id = get_or_create_reputation_token(winner, ctx.metadata().slot_time(), state, logger)?;
             if let Some(mut winner_data) = state.tokens.get(&winner_token_id).map(|d| d.clone()) {
                 let old_score = winner_data.score;
                 winner_data.disputes_won += 1;
                 winner_data.last_activity = ctx.metadata().slot_time();
+                winner_data.decay_periods_applied = 0;
                 
                 // Reputation increase for winning dispute
                 let reputation_increase = 20 + (dispute_value.micro_ccd() / 1_000_000).min(30);
                 winner_data.score += reputation_increase;
+                state.breakdowns.entry(winner).or_insert(ReputationBreakdown::default()).modify(|breakdown| {
+                    breakdown.credit(winner_is_buyer, category.as_deref(), reputation_increase)
+                });
                 
                 state.tokens.insert(winner_token_id, winner_data.clone());
-                state.reputation_scores.insert(winner, winner_data.score);
+                record_score(
+                    &mut state.reputation_scores,
+                    &mut state.score_history,
+                    winner,
+                    ctx.metadata().slot_time(),
+                    winner_data.score,
+                    UpdateReason::DisputeWon,
+                );
                 
                 logger.log(&ReputationEvent::ReputationUpdated {
                     account: winner,
@@ -381,18 +750,29 @@ fn update<S: HasStateApi>(
             }
             
             // Update loser's reputation
-            let loser_token_id = get_or_create_reputation_token(loser, state, logger)?;
+            let loser_token_id = get_or_create_reputation_token(loser, ctx.metadata().slot_time(), state, logger)?;
             if let Some(mut loser_data) = state.tokens.get(&loser_token_id).map(|d| d.clone()) {
                 let old_score = loser_data.score;
                 loser_data.disputes_lost += 1;
                 loser_data.last_activity = ctx.metadata().slot_time();
+                loser_data.decay_periods_applied = 0;
                 
                 // Reputation decrease for losing dispute
                 let reputation_decrease = 30 + (dispute_value.micro_ccd() / 1_000_000).min(50);
                 loser_data.score = loser_data.score.saturating_sub(reputation_decrease);
+                state.breakdowns.entry(loser).or_insert(ReputationBreakdown::default()).modify(|breakdown| {
+                    breakdown.debit(!winner_is_buyer, category.as_deref(), reputation_decrease)
+                });
                 
                 state.tokens.insert(loser_token_id, loser_data.clone());
-                state.reputation_scores.insert(loser, loser_data.score);
+                record_score(
+                    &mut state.reputation_scores,
+                    &mut state.score_history,
+                    loser,
+                    ctx.metadata().slot_time(),
+                    loser_data.score,
+                    UpdateReason::DisputeLost,
+                );
                 
                 logger.log(&ReputationEvent::ReputationUpdated {
                     account: loser,
@@ -405,41 +785,101 @@ fn update<S: HasStateApi>(
             Ok(())
         }
         
-        ReputationUpdateParams::SetVerificationLevel { account, level } => {
-            // Only admin can set verification levels
-            if ctx.sender() != state.admin {
+        ReputationUpdateParams::UpdateFromJurors { outcomes } => {
+            // Only dispute contract or admin can update juror sub-scores
+            if let Some(dispute_contract) = state.dispute_contract {
+                if ctx.sender() != Address::Contract(dispute_contract) && ctx.sender() != state.admin {
+                    return Err(ReputationError::Unauthorized);
+                }
+            } else if ctx.sender() != state.admin {
                 return Err(ReputationError::Unauthorized);
             }
             
-            let token_id = get_or_create_reputation_token(account, state, logger)?;
+            // Juror conduct only affects the juror sub-score, not the overall score
+            for outcome in outcomes {
+                state.breakdowns.entry(outcome.juror).or_insert(ReputationBreakdown::default()).modify(|breakdown| {
+                    if outcome.voted_with_resolution {
+                        breakdown.as_juror += JUROR_ALIGNED_POINTS;
+                    } else {
+                        breakdown.as_juror = breakdown.as_juror.saturating_sub(JUROR_MISALIGNED_PENALTY);
+                    }
+                });
+            }
+            
+            Ok(())
+        }
+        
+        ReputationUpdateParams::SetVerificationLevel {
+            account,
+            level,
+            verified_at,
+        } => {
+            // Only verifier registry or admin can set verification levels
+            if let Some(verifier_registry) = state.verifier_registry {
+                if ctx.sender() != Address::Contract(verifier_registry) && ctx.sender() != state.admin {
+                    return Err(ReputationError::Unauthorized);
+                }
+            } else if ctx.sender() != state.admin {
+                return Err(ReputationError::Unauthorized);
+            }
+            
+            let now = ctx.metadata().slot_time();
+            let expires_at = verified_at
+                .checked_add(Duration::from_days(VERIFICATION_VALIDITY_DAYS))
+                .ok_or(ReputationError::InvalidVerificationLevel)?;
+            if verified_at > now || (level != VerificationLevel::None && now > expires_at) {
+                return Err(ReputationError::InvalidVerificationLevel);
+            }
+            
+            // Clearing the level of an account without a token is a no-op, so syncs cannot mint tokens
+            if level == VerificationLevel::None && state.account_tokens.get(&account).is_none() {
+                return Ok(());
+            }
+            
+            let token_id = get_or_create_reputation_token(account, now, state, logger)?;
             let mut token_data = state.tokens.get(&token_id).ok_or(ReputationError::TokenNotFound)?.clone();
             
             let old_level = token_data.verification_level.clone();
             let old_score = token_data.score;
-            token_data.verification_level = level.clone();
             
-            // Bonus reputation for verification
-            let verification_bonus = match level {
-                VerificationLevel::None => 0,
-                VerificationLevel::Basic => 50,
-                VerificationLevel::Full => 150,
-                VerificationLevel::Professional => 300,
-            };
+            // Only upgrades add to the score; downgrades claw back the difference
+            let old_bonus = verification_bonus(&old_level);
+            let new_bonus = verification_bonus(&level);
+            if new_bonus > old_bonus {
+                token_data.score += new_bonus - old_bonus;
+            } else {
+                token_data.score = token_data.score.saturating_sub(old_bonus - new_bonus);
+            }
             
-            if verification_bonus > 0 {
-                token_data.score += verification_bonus;
+            // Setting a level again with a newer verification renews it without another bonus
+            token_data.verification_level = level.clone();
+            if level == VerificationLevel::None {
+                token_data.verified_at = None;
+                token_data.expires_at = None;
+            } else {
+                token_data.verified_at = Some(verified_at);
+                token_data.expires_at = Some(expires_at);
             }
             
             state.tokens.insert(token_id, token_data.clone());
-            state.reputation_scores.insert(account, token_data.score);
             
-            logger.log(&ReputationEvent::VerificationChanged {
-                account,
-                old_level,
-                new_level: level,
-            })?;
+            if old_level != level {
+                logger.log(&ReputationEvent::VerificationChanged {
+                    account,
+                    old_level,
+                    new_level: level,
+                })?;
+            }
             
-            if verification_bonus > 0 {
+            if token_data.score != old_score {
+                record_score(
+                    &mut state.reputation_scores,
+                    &mut state.score_history,
+                    account,
+                    now,
+                    token_data.score,
+                    UpdateReason::Verification,
+                );
                 logger.log(&ReputationEvent::ReputationUpdated {
                     account,
                     old_score,
@@ -460,35 +900,140 @@ fn update<S: HasStateApi>(
             Ok(())
         }
         
-        ReputationUpdateParams::DecayInactiveReputation => {
+        ReputationUpdateParams::SetDisputeContract { contract_address } => {
+            if ctx.sender() != state.admin {
+                return Err(ReputationError::Unauthorized);
+            }
+            
+            state.dispute_contract = Some(contract_address);
+            Ok(())
+        }
+        
+        ReputationUpdateParams::SetVerifierRegistry { contract_address } => {
+            if ctx.sender() != state.admin {
+                return Err(ReputationError::Unauthorized);
+            }
+            
+            state.verifier_registry = Some(contract_address);
+            Ok(())
+        }
+        
+        ReputationUpdateParams::SetMetadataBaseUrl { url } => {
+            if ctx.sender() != state.admin {
+                return Err(ReputationError::Unauthorized);
+            }
+            
+            state.metadata_base_url = url;
+            Ok(())
+        }
+        
+        ReputationUpdateParams::SetCategory { category, enabled } => {
+            if ctx.sender() != state.admin {
+                return Err(ReputationError::Unauthorized);
+            }
+            
+            // Removed categories stop scoring, existing sub-scores are kept
+            let registered = state.categories.contains(&category);
+            if enabled && !registered {
+                if category.len() > MAX_CATEGORY_LENGTH || state.categories.len() >= MAX_CATEGORIES {
+                    return Err(ReputationError::InvalidCategory);
+                }
+                state.categories.push(category);
+            } else if !enabled {
+                state.categories.retain(|c| *c != category);
+            }
+            Ok(())
+        }
+        
+        ReputationUpdateParams::DecayBatch { start_token, max_items } => {
             // Anyone can trigger reputation decay (gas paid by caller)
             let current_time = ctx.metadata().slot_time();
-            let decay_threshold = Duration::from_days(90); // 90 days of inactivity
             
-            // This is simplified - in a real implementation, you'd want to iterate more efficiently
-            // and possibly limit the number of accounts processed per call
+            let start_token = start_token.unwrap_or(state.decay_cursor);
+            let batch_size = max_items.min(MAX_DECAY_BATCH_SIZE);
+            let end_token = start_token.saturating_add(batch_size).min(state.next_token_id);
             
-            for (token_id, token_data) in state.tokens.iter_mut() {
-                if current_time.duration_since(token_data.last_activity) > decay_threshold {
+            let mut accounts_decayed = 0u32;
+            let mut token_id = start_token;
+            while token_id < end_token {
+                if let Some(mut token_data) = state.tokens.get_mut(&token_id) {
+                    // Only periods that have not been decayed yet are applied
+                    if apply_inactivity_decay(&mut token_data, current_time) {
+                        record_score(
+                            &mut state.reputation_scores,
+                            &mut state.score_history,
+                            token_data.owner,
+                            current_time,
+                            token_data.score,
+                            UpdateReason::Decay,
+                        );
+                        accounts_decayed += 1;
+                    }
+                }
+                token_id += 1;
+            }
+            
+            // Wrap around once the last token has been processed
+            state.decay_cursor = if end_token >= state.next_token_id { 0 } else { end_token };
+            
+            logger.log(&ReputationEvent::DecayBatchProcessed {
+                start_token,
+                end_token,
+                accounts_decayed,
+            })?;
+            
+            Ok(())
+        }
+        
+        ReputationUpdateParams::ExpireVerifications { start_token, max_items } => {
+            // Anyone can trigger verification expiry (gas paid by caller)
+            let current_time = ctx.metadata().slot_time();
+            
+            let start_token = start_token.unwrap_or(state.expiry_cursor);
+            let batch_size = max_items.min(MAX_EXPIRY_BATCH_SIZE);
+            let end_token = start_token.saturating_add(batch_size).min(state.next_token_id);
+            
+            let mut accounts_expired = 0u32;
+            let mut token_id = start_token;
+            while token_id < end_token {
+                if let Some(mut token_data) = state.tokens.get_mut(&token_id) {
                     let old_score = token_data.score;
-                    // Decay 5% of current reputation
-                    let decay_amount = token_data.score / 20;
-                    token_data.score = token_data.score.saturating_sub(decay_amount);
-                    
-                    // Update reputation score mapping
-                    state.reputation_scores.insert(token_data.owner, token_data.score);
-                    
-                    if decay_amount > 0 {
+                    if let Some(old_level) = apply_verification_expiry(&mut token_data, current_time) {
+                        record_score(
+                            &mut state.reputation_scores,
+                            &mut state.score_history,
+                            token_data.owner,
+                            current_time,
+                            token_data.score,
+                            UpdateReason::VerificationExpired,
+                        );
+                        accounts_expired += 1;
+                        
+                        logger.log(&ReputationEvent::VerificationChanged {
+                            account: token_data.owner,
+                            old_level,
+                            new_level: VerificationLevel::None,
+                        })?;
                         logger.log(&ReputationEvent::ReputationUpdated {
                             account: token_data.owner,
                             old_score,
                             new_score: token_data.score,
-                            reason: UpdateReason::Decay,
+                            reason: UpdateReason::VerificationExpired,
                         })?;
                     }
                 }
+                token_id += 1;
             }
             
+            // Wrap around once the last token has been processed
+            state.expiry_cursor = if end_token >= state.next_token_id { 0 } else { end_token };
+            
+            logger.log(&ReputationEvent::ExpiryBatchProcessed {
+                start_token,
+                end_token,
+                accounts_expired,
+            })?;
+            
             Ok(())
         }
     }
@@ -533,9 +1078,188 @@ fn get_score<S: HasStateApi>(
     Ok(state.reputation_scores.get(&account).copied().unwrap_or(0))
 }
 
+/// Get an account's sub-scores per role and product category
+#[receive(
+    contract = "credify_reputation",
+    name = "get_reputation_breakdown",
+    parameter = "AccountAddress",
+    return_value = "ReputationBreakdown",
+    error = "ReputationError"
+)]
+fn get_reputation_breakdown<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    host: &Host<ReputationState<S>>,
+) -> ContractResult<ReputationBreakdown> {
+    let account: AccountAddress = ctx.parameter_cursor().get().map_err(|_| ReputationError::ParseError)?;
+    let state = host.state();
+    
+    Ok(state.breakdowns.get(&account).map(|b| b.clone()).unwrap_or_default())
+}
+
+/// Get an account's reputation score at a point in time
+///
+/// Accounts without score history (scored before history was kept and unchanged since) return
+/// their current score.
+#[receive(
+    contract = "credify_reputation",
+    name = "get_score_at",
+    parameter = "ScoreAtQuery",
+    return_value = "u64",
+    error = "ReputationError"
+)]
+fn get_score_at<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    host: &Host<ReputationState<S>>,
+) -> ContractResult<u64> {
+    let query: ScoreAtQuery = ctx.parameter_cursor().get().map_err(|_| ReputationError::ParseError)?;
+    let state = host.state();
+    
+    Ok(match state.score_history.get(&query.account) {
+        Some(history) => history.score_at(query.timestamp),
+        None => state.reputation_scores.get(&query.account).copied().unwrap_or(0),
+    })
+}
+
+/// Get an account's retained score checkpoints, oldest first
+#[receive(
+    contract = "credify_reputation",
+    name = "get_score_history",
+    parameter = "AccountAddress",
+    return_value = "Vec<ScoreCheckpoint>",
+    error = "ReputationError"
+)]
+fn get_score_history<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    host: &Host<ReputationState<S>>,
+) -> ContractResult<Vec<ScoreCheckpoint>> {
+    let account: AccountAddress = ctx.parameter_cursor().get().map_err(|_| ReputationError::ParseError)?;
+    let state = host.state();
+    
+    Ok(state
+        .score_history
+        .get(&account)
+        .map(|history| history.iter().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// Length of one inactivity period after which reputation decays
+const DECAY_PERIOD_DAYS: u64 = 90;
+
+/// Maximum number of tokens processed by a single decay batch
+const MAX_DECAY_BATCH_SIZE: u32 = 100;
+
+/// Decay reputation by 5% for every full inactivity period not decayed yet
+///
+/// Returns whether the score changed.
+fn apply_inactivity_decay(token_data: &mut ReputationData, now: Timestamp) -> bool {
+    let inactive_for = match now.duration_since(token_data.last_activity) {
+        Some(duration) => duration,
+        None => return false,
+    };
+    let elapsed_periods = inactive_for.millis() / Duration::from_days(DECAY_PERIOD_DAYS).millis();
+    if elapsed_periods <= token_data.decay_periods_applied {
+        return false;
+    }
+    
+    let old_score = token_data.score;
+    for _ in token_data.decay_periods_applied..elapsed_periods {
+        // Decay 5% of current reputation
+        token_data.score -= token_data.score / 20;
+        // Below 20 points the 5% decay rounds down to zero
+        if token_data.score < 20 {
+            break;
+        }
+    }
+    token_data.decay_periods_applied = elapsed_periods;
+    
+    token_data.score != old_score
+}
+
+/// How long a verification level stays valid (KYC validity of 12 months)
+pub const VERIFICATION_VALIDITY_DAYS: u64 = 365;
+
+/// Maximum number of tokens processed by a single expiry batch
+const MAX_EXPIRY_BATCH_SIZE: u32 = 100;
+
+/// Score bonus held while an account has a verification level
+fn verification_bonus(level: &VerificationLevel) -> u64 {
+    match level {
+        VerificationLevel::None => 0,
+        VerificationLevel::Basic => 50,
+        VerificationLevel::Full => 150,
+        VerificationLevel::Professional => 300,
+    }
+}
+
+/// Whether the account's verification level has expired
+fn verification_expired(token_data: &ReputationData, now: Timestamp) -> bool {
+    token_data.expires_at.map_or(false, |expires_at| now > expires_at)
+}
+
+/// Reset an expired verification level and claw back its bonus
+///
+/// Returns the expired level, if any.
+fn apply_verification_expiry(token_data: &mut ReputationData, now: Timestamp) -> Option<VerificationLevel> {
+    if !verification_expired(token_data, now) {
+        return None;
+    }
+    
+    let expired_level = core::mem::replace(&mut token_data.verification_level, VerificationLevel::None);
+    token_data.score = token_data.score.saturating_sub(verification_bonus(&expired_level));
+    token_data.verified_at = None;
+    token_data.expires_at = None;
+    
+    Some(expired_level)
+}
+
+/// Maximum number of product categories tracked per account
+const MAX_CATEGORIES: usize = 20;
+
+/// Maximum length of a product category name
+pub const MAX_CATEGORY_LENGTH: usize = 64;
+
+/// Juror sub-score points for voting with the resolution
+const JUROR_ALIGNED_POINTS: u64 = 10;
+
+/// Juror sub-score penalty for voting against the resolution or not revealing
+const JUROR_MISALIGNED_PENALTY: u64 = 15;
+
+/// Maximum number of score checkpoints kept per account
+const MAX_SCORE_CHECKPOINTS: usize = 32;
+
+/// Set an account's score and record a checkpoint for it
+fn record_score<S: HasStateApi>(
+    reputation_scores: &mut StateMap<AccountAddress, u64, S>,
+    score_history: &mut StateMap<AccountAddress, ScoreHistory, S>,
+    account: AccountAddress,
+    timestamp: Timestamp,
+    score: u64,
+    reason: UpdateReason,
+) {
+    let previous_score = reputation_scores.insert(account, score);
+    score_history.entry(account).or_insert(ScoreHistory::default()).modify(|history| {
+        // Accounts scored before history was kept start from their previous score
+        if history.checkpoints.is_empty() {
+            if let Some(previous_score) = previous_score {
+                history.push(ScoreCheckpoint {
+                    timestamp,
+                    score: previous_score,
+                    reason: UpdateReason::Snapshot,
+                });
+            }
+        }
+        history.push(ScoreCheckpoint {
+            timestamp,
+            score,
+            reason,
+        })
+    });
+}
+
 // Helper function to get or create reputation token
 fn get_or_create_reputation_token<S: HasStateApi>(
     account: AccountAddress,
+    now: Timestamp,
     state: &mut ReputationState<S>,
     logger: &mut Logger,
 ) -> ContractResult<TokenIdU32> {
@@ -553,14 +1277,24 @@ fn get_or_create_reputation_token<S: HasStateApi>(
             seller_transactions: 0,
             disputes_lost: 0,
             disputes_won: 0,
-            last_activity: Timestamp::from_timestamp_millis(0), // Will be set when first used
+            last_activity: now,
+            decay_periods_applied: 0,
             total_transaction_value: 0,
             verification_level: VerificationLevel::None,
+            verified_at: None,
+            expires_at: None,
         };
         
         state.tokens.insert(token_id, reputation_data);
-        state.reputation_scores.insert(account, state.base_reputation);
         state.account_tokens.insert(account, token_id);
+        record_score(
+            &mut state.reputation_scores,
+            &mut state.score_history,
+            account,
+            now,
+            state.base_reputation,
+            UpdateReason::Created,
+        );
         
         logger.log(&ReputationEvent::TokenCreated {
             token_id,
@@ -570,4 +1304,56 @@ fn get_or_create_reputation_token<S: HasStateApi>(
         
         Ok(token_id)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn checkpoint(timestamp: u64, score: u64, reason: UpdateReason) -> ScoreCheckpoint {
+        ScoreCheckpoint {
+            timestamp: Timestamp::from_timestamp_millis(timestamp),
+            score,
+            reason,
+        }
+    }
+    
+    #[test]
+    fn score_at_returns_latest_checkpoint_before_timestamp() {
+        let mut history = ScoreHistory::default();
+        history.push(checkpoint(1_000, 100, UpdateReason::Created));
+        history.push(checkpoint(2_000, 120, UpdateReason::Transaction));
+        history.push(checkpoint(3_000, 90, UpdateReason::DisputeLost));
+        
+        assert_eq!(history.score_at(Timestamp::from_timestamp_millis(500)), 0);
+        assert_eq!(history.score_at(Timestamp::from_timestamp_millis(1_500)), 100);
+        assert_eq!(history.score_at(Timestamp::from_timestamp_millis(2_500)), 120);
+        assert_eq!(history.score_at(Timestamp::from_timestamp_millis(3_000)), 90);
+    }
+    
+    #[test]
+    fn score_at_is_not_inflated_by_overflowing_the_buffer() {
+        let mut history = ScoreHistory::default();
+        history.push(checkpoint(1_000, 100, UpdateReason::Created));
+        history.push(checkpoint(2_000, 120, UpdateReason::Transaction));
+        let lookup = Timestamp::from_timestamp_millis(2_500);
+        
+        // A burst of score increases after the lookup timestamp overwrites all earlier checkpoints
+        for i in 0..(MAX_SCORE_CHECKPOINTS as u64 + 8) {
+            history.push(checkpoint(3_000 + i, 200 + i, UpdateReason::Transaction));
+        }
+        assert!(history.iter().all(|c| c.timestamp > lookup));
+        
+        assert_eq!(history.score_at(lookup), 100);
+        assert_eq!(history.score_at(Timestamp::from_timestamp_millis(500)), 0);
+    }
+    
+    #[test]
+    fn score_at_before_snapshot_uses_lowest_retained_score() {
+        let mut history = ScoreHistory::default();
+        history.push(checkpoint(5_000, 150, UpdateReason::Snapshot));
+        history.push(checkpoint(5_000, 170, UpdateReason::Transaction));
+        
+        assert_eq!(history.score_at(Timestamp::from_timestamp_millis(1_000)), 150);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,447 @@
+/*!
+CREDIFY Verifier Registry Smart Contract
+
+This contract keeps the set of trusted identity verifiers shared by the escrow and
+reputation contracts. The admin registers verifier accounts together with the
+verification levels each of them may issue, and verifiers issue and revoke
+attestations for accounts.
+
+Features:
+- Admin-managed verifier accounts
+- Per-level issuing permissions with expiry
+- Attestation issuance and revocation
+- Verification levels propagated to the reputation contract
+*/
+
+use concordium_std::*;
+
+use crate::reputation::{ReputationUpdateParams, VerificationLevel, VERIFICATION_VALIDITY_DAYS};
+
+// Contract state
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct VerifierRegistryState<S: HasStateApi = StateApi> {
+    /// Admin account that manages verifiers
+    pub admin: AccountAddress,
+    /// Map of verifier account to verifier details
+    pub verifiers: StateMap<AccountAddress, VerifierInfo, S>,
+    /// Map of account to attestations issued for it
+    pub attestations: StateMap<AccountAddress, Vec<Attestation>, S>,
+    /// Reputation contract receiving verification level changes
+    pub reputation_contract: Option<ContractAddress>,
+}
+
+// Registered verifier
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct VerifierInfo {
+    /// Display name of the verifier
+    pub name: String,
+    /// Verification levels the verifier may issue
+    pub permissions: Vec<VerifierPermission>,
+    /// Timestamp of registration
+    pub registered_at: Timestamp,
+}
+
+// Permission to issue a verification level
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct VerifierPermission {
+    /// Level the verifier may issue
+    pub level: VerificationLevel,
+    /// Timestamp after which the permission is no longer valid
+    pub expires_at: Timestamp,
+}
+
+// Attestation issued by a verifier
+#[derive(Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    /// Verifier that issued the attestation
+    pub verifier: AccountAddress,
+    /// Attested verification level
+    pub level: VerificationLevel,
+    /// Timestamp of issuance
+    pub issued_at: Timestamp,
+    /// Whether the verifier revoked the attestation
+    pub revoked: bool,
+}
+
+// Query whether a verifier may issue a level
+#[derive(Serialize, SchemaType)]
+pub struct VerifierQuery {
+    pub verifier: AccountAddress,
+    pub level: VerificationLevel,
+}
+
+// Contract initialization parameters
+#[derive(Serialize, SchemaType)]
+pub struct InitParams {
+    pub admin: AccountAddress,
+}
+
+// Contract update parameters
+#[derive(Serialize, SchemaType)]
+pub enum UpdateParams {
+    /// Register a verifier or replace its permissions (admin only)
+    RegisterVerifier {
+        verifier: AccountAddress,
+        name: String,
+        permissions: Vec<VerifierPermission>,
+    },
+    /// Remove a verifier (admin only)
+    RemoveVerifier {
+        verifier: AccountAddress,
+    },
+    /// Attest a verification level for an account (verifier action)
+    IssueAttestation {
+        subject: AccountAddress,
+        level: VerificationLevel,
+    },
+    /// Revoke a verifier's attestation for an account (verifier or admin action)
+    RevokeAttestation {
+        subject: AccountAddress,
+        verifier: AccountAddress,
+    },
+    /// Push an account's current verification level to the reputation contract (anyone)
+    SyncVerificationLevel {
+        account: AccountAddress,
+    },
+    /// Set reputation contract address (admin only)
+    SetReputationContract {
+        contract_address: ContractAddress,
+    },
+}
+
+// Contract errors
+#[derive(Debug, PartialEq, Eq, Reject, Serialize, SchemaType)]
+pub enum RegistryError {
+    /// Only admin or the verifier can perform this action
+    Unauthorized,
+    /// Verifier not registered
+    VerifierNotFound,
+    /// Verifier may not issue this level (or the permission expired)
+    LevelNotPermitted,
+    /// Attestation not found
+    AttestationNotFound,
+    /// Parse error
+    ParseError,
+    /// Event could not be logged
+    LogError,
+}
+
+impl From<LogError> for RegistryError {
+    fn from(_: LogError) -> Self {
+        RegistryError::LogError
+    }
+}
+
+// Contract events
+#[derive(Debug, Serialize, SchemaType)]
+pub enum RegistryEvent {
+    /// Verifier registered or permissions updated
+    VerifierRegistered {
+        verifier: AccountAddress,
+        permissions: Vec<VerificationLevel>,
+    },
+    /// Verifier removed
+    VerifierRemoved {
+        verifier: AccountAddress,
+    },
+    /// Attestation issued
+    AttestationIssued {
+        subject: AccountAddress,
+        verifier: AccountAddress,
+        level: VerificationLevel,
+    },
+    /// Attestation revoked, with the level the account was downgraded to
+    AttestationRevoked {
+        subject: AccountAddress,
+        verifier: AccountAddress,
+        new_level: VerificationLevel,
+    },
+}
+
+type ContractResult<T> = Result<T, RegistryError>;
+
+/// Initialize the verifier registry contract
+#[init(contract = "credify_verifier_registry", parameter = "InitParams")]
+fn init(ctx: &InitContext, state_builder: &mut StateBuilder) -> InitResult<VerifierRegistryState> {
+    let params: InitParams = ctx.parameter_cursor().get()?;
+    
+    let state = VerifierRegistryState {
+        admin: params.admin,
+        verifiers: state_builder.new_map(),
+        attestations: state_builder.new_map(),
+        reputation_contract: None,
+    };
+    
+    Ok(state)
+}
+
+/// Handle contract updates
+#[receive(
+    contract = "credify_verifier_registry",
+    name = "update",
+    parameter = "UpdateParams",
+    error = "RegistryError",
+    enable_logger,
+    mutable
+)]
+fn update(
+    ctx: &ReceiveContext,
+    host: &mut Host<VerifierRegistryState>,
+    logger: &mut Logger,
+) -> ContractResult<()> {
+    let params: UpdateParams = ctx.parameter_cursor().get().map_err(|_| RegistryError::ParseError)?;
+    let now = ctx.metadata().slot_time();
+    let state = host.state_mut();
+    
+    match params {
+        UpdateParams::RegisterVerifier {
+            verifier,
+            name,
+            permissions,
+        } => {
+            if ctx.sender() != Address::Account(state.admin) {
+                return Err(RegistryError::Unauthorized);
+            }
+            
+            let levels = permissions.iter().map(|p| p.level.clone()).collect();
+            state.verifiers.insert(verifier, VerifierInfo {
+                name,
+                permissions,
+                registered_at: now,
+            });
+            
+            logger.log(&RegistryEvent::VerifierRegistered {
+                verifier,
+                permissions: levels,
+            })?;
+            
+            Ok(())
+        }
+        
+        UpdateParams::RemoveVerifier { verifier } => {
+            if ctx.sender() != Address::Account(state.admin) {
+                return Err(RegistryError::Unauthorized);
+            }
+            
+            // Attestations by a removed verifier stop counting; accounts are downgraded on their next sync
+            if state.verifiers.remove_and_get(&verifier).is_none() {
+                return Err(RegistryError::VerifierNotFound);
+            }
+            
+            logger.log(&RegistryEvent::VerifierRemoved { verifier })?;
+            
+            Ok(())
+        }
+        
+        UpdateParams::IssueAttestation { subject, level } => {
+            let verifier = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => return Err(RegistryError::Unauthorized),
+            };
+            
+            if !may_issue(state, verifier, &level, now) {
+                return Err(RegistryError::LevelNotPermitted);
+            }
+            
+            // A new attestation replaces the verifier's previous one for this account
+            state.attestations.entry(subject).or_insert(Vec::new()).modify(|attestations| {
+                attestations.retain(|a| a.verifier != verifier);
+                attestations.push(Attestation {
+                    verifier,
+                    level: level.clone(),
+                    issued_at: now,
+                    revoked: false,
+                });
+            });
+            
+            logger.log(&RegistryEvent::AttestationIssued {
+                subject,
+                verifier,
+                level,
+            })?;
+            
+            sync_verification_level(host, subject, now);
+            
+            Ok(())
+        }
+        
+        UpdateParams::RevokeAttestation { subject, verifier } => {
+            if ctx.sender() != Address::Account(verifier) && ctx.sender() != Address::Account(state.admin) {
+                return Err(RegistryError::Unauthorized);
+            }
+            
+            let mut attestations = state.attestations.get_mut(&subject).ok_or(RegistryError::AttestationNotFound)?;
+            let attestation = attestations
+                .iter_mut()
+                .find(|a| a.verifier == verifier && !a.revoked)
+                .ok_or(RegistryError::AttestationNotFound)?;
+            attestation.revoked = true;
+            drop(attestations);
+            
+            let new_level = effective_level(host.state(), subject, now);
+            
+            logger.log(&RegistryEvent::AttestationRevoked {
+                subject,
+                verifier,
+                new_level,
+            })?;
+            
+            sync_verification_level(host, subject, now);
+            
+            Ok(())
+        }
+        
+        UpdateParams::SyncVerificationLevel { account } => {
+            sync_verification_level(host, account, now);
+            Ok(())
+        }
+        
+        UpdateParams::SetReputationContract { contract_address } => {
+            if ctx.sender() != Address::Account(state.admin) {
+                return Err(RegistryError::Unauthorized);
+            }
+            
+            state.reputation_contract = Some(contract_address);
+            Ok(())
+        }
+    }
+}
+
+/// Get an account's current verification level
+#[receive(
+    contract = "credify_verifier_registry",
+    name = "get_verification_level",
+    parameter = "AccountAddress",
+    return_value = "VerificationLevel",
+    error = "RegistryError"
+)]
+fn get_verification_level(
+    ctx: &ReceiveContext,
+    host: &Host<VerifierRegistryState>,
+) -> ContractResult<VerificationLevel> {
+    let account: AccountAddress = ctx.parameter_cursor().get().map_err(|_| RegistryError::ParseError)?;
+    Ok(effective_level(host.state(), account, ctx.metadata().slot_time()))
+}
+
+/// Get an account's effective attestation (highest unrevoked, unexpired and still permitted)
+#[receive(
+    contract = "credify_verifier_registry",
+    name = "get_effective_attestation",
+    parameter = "AccountAddress",
+    return_value = "Option<Attestation>",
+    error = "RegistryError"
+)]
+fn get_effective_attestation(
+    ctx: &ReceiveContext,
+    host: &Host<VerifierRegistryState>,
+) -> ContractResult<Option<Attestation>> {
+    let account: AccountAddress = ctx.parameter_cursor().get().map_err(|_| RegistryError::ParseError)?;
+    Ok(effective_attestation(host.state(), account, ctx.metadata().slot_time()))
+}
+
+/// Check whether a verifier currently may issue a verification level
+#[receive(
+    contract = "credify_verifier_registry",
+    name = "can_attest",
+    parameter = "VerifierQuery",
+    return_value = "bool",
+    error = "RegistryError"
+)]
+fn can_attest(ctx: &ReceiveContext, host: &Host<VerifierRegistryState>) -> ContractResult<bool> {
+    let query: VerifierQuery = ctx.parameter_cursor().get().map_err(|_| RegistryError::ParseError)?;
+    Ok(may_issue(host.state(), query.verifier, &query.level, ctx.metadata().slot_time()))
+}
+
+/// Get verifier details
+#[receive(
+    contract = "credify_verifier_registry",
+    name = "get_verifier",
+    parameter = "AccountAddress",
+    return_value = "Option<VerifierInfo>",
+    error = "RegistryError"
+)]
+fn get_verifier(ctx: &ReceiveContext, host: &Host<VerifierRegistryState>) -> ContractResult<Option<VerifierInfo>> {
+    let verifier: AccountAddress = ctx.parameter_cursor().get().map_err(|_| RegistryError::ParseError)?;
+    Ok(host.state().verifiers.get(&verifier).map(|info| info.clone()))
+}
+
+/// Get attestations issued for an account
+#[receive(
+    contract = "credify_verifier_registry",
+    name = "get_attestations",
+    parameter = "AccountAddress",
+    return_value = "Vec<Attestation>",
+    error = "RegistryError"
+)]
+fn get_attestations(ctx: &ReceiveContext, host: &Host<VerifierRegistryState>) -> ContractResult<Vec<Attestation>> {
+    let account: AccountAddress = ctx.parameter_cursor().get().map_err(|_| RegistryError::ParseError)?;
+    Ok(host.state().attestations.get(&account).map(|a| a.clone()).unwrap_or_default())
+}
+
+// Helper functions
+
+/// Whether a verifier holds an unexpired permission for a level
+fn may_issue(state: &VerifierRegistryState, verifier: AccountAddress, level: &VerificationLevel, now: Timestamp) -> bool {
+    state.verifiers.get(&verifier).map_or(false, |info| {
+        info.permissions.iter().any(|p| p.level == *level && now <= p.expires_at)
+    })
+}
+
+/// Highest-level unexpired attestation by a verifier that still holds the permission for it
+fn effective_attestation(state: &VerifierRegistryState, account: AccountAddress, now: Timestamp) -> Option<Attestation> {
+    let validity = Duration::from_days(VERIFICATION_VALIDITY_DAYS);
+    state.attestations.get(&account).and_then(|attestations| {
+        attestations
+            .iter()
+            .filter(|a| {
+                !a.revoked
+                    && a.issued_at.checked_add(validity).map_or(false, |expires_at| now <= expires_at)
+                    && may_issue(state, a.verifier, &a.level, now)
+            })
+            .max_by_key(|a| level_rank(&a.level))
+            .cloned()
+    })
+}
+
+/// Level of the account's effective attestation
+fn effective_level(state: &VerifierRegistryState, account: AccountAddress, now: Timestamp) -> VerificationLevel {
+    effective_attestation(state, account, now).map_or(VerificationLevel::None, |a| a.level)
+}
+
+/// Order of verification levels from lowest to highest
+fn level_rank(level: &VerificationLevel) -> u8 {
+    match level {
+        VerificationLevel::None => 0,
+        VerificationLevel::Basic => 1,
+        VerificationLevel::Full => 2,
+        VerificationLevel::Professional => 3,
+    }
+}
+
+/// Send an account's current verification level to the reputation contract
+///
+/// Best effort: a failing reputation contract must not block issuing or revoking attestations.
+fn sync_verification_level(host: &mut Host<VerifierRegistryState>, account: AccountAddress, now: Timestamp) {
+    let reputation_contract = match host.state().reputation_contract {
+        Some(contract) => contract,
+        None => return,
+    };
+    
+    // The level expires in the reputation contract relative to when it was attested
+    let (level, verified_at) = match effective_attestation(host.state(), account, now) {
+        Some(attestation) => (attestation.level, attestation.issued_at),
+        None => (VerificationLevel::None, now),
+    };
+    let params = ReputationUpdateParams::SetVerificationLevel {
+        account,
+        level,
+        verified_at,
+    };
+    let _ = host.invoke_contract(
+        &reputation_contract,
+        &params,
+        EntrypointName::new_unchecked("update"),
+        Amount::zero(),
+    );
+}
\ No newline at end of file